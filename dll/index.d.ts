@@ -3,6 +3,213 @@
 
 /* auto-generated by NAPI-RS */
 
+/**
+ * Cube coordinates of a hex. The invariant `q + r + s == 0` always holds, functions taking
+ * cube coordinates reject ones that break it.
+ */
+export interface CubeCoord {
+  q: number
+  r: number
+  s: number
+}
+/** Axial coordinates of a hex (cube coordinates without the redundant `s`). */
+export interface AxialCoord {
+  q: number
+  r: number
+}
+/** Axes of a hex grid, named after the cube coordinate that stays fixed when reflecting across them. */
+export const enum HexAxis {
+  Q = 0,
+  R = 1,
+  S = 2
+}
+export function cubeAdd(a: CubeCoord, b: CubeCoord): CubeCoord
+export function cubeSubtract(a: CubeCoord, b: CubeCoord): CubeCoord
+export function cubeScale(cube: CubeCoord, factor: number): CubeCoord
+export function cubeDistance(a: CubeCoord, b: CubeCoord): number
+export function cubeToAxial(cube: CubeCoord): AxialCoord
+export function axialToCube(axial: AxialCoord): CubeCoord
+export interface LineOfSightOptions {
+  /** Impassable hexes block the view (default: true) */
+  blockImpassable?: boolean
+  /** Battleground hexes block the view (default: false) */
+  blockBattleground?: boolean
+  /** Hexes with one of these security indexes block the view */
+  blockingSecurityIndexes?: Array<string>
+  /** Whether the target hex itself can block the view (default: false) */
+  includeTarget?: boolean
+}
+export interface LineOfSight {
+  visible: boolean
+  blockingHex?: Hex
+}
+export interface PixelPoint {
+  x: number
+  y: number
+}
+/**
+ * Flat-top hex layout matching the grid's odd-q columns: odd columns are drawn half a hex
+ * lower and `y` grows downwards.
+ */
+export declare class HexLayout {
+  /**
+   * `size` is the distance from a hex center to its corners along each axis,
+   * `origin` is the pixel position of the center of hex (0, 0).
+   */
+  static new(size: PixelPoint, origin: PixelPoint): HexLayout
+  hexToPixel(point: Point): PixelPoint
+  pixelToHex(pixel: PixelPoint): Point
+  /** Corners of the hex, starting from the east one and going clockwise on screen. */
+  hexCorners(point: Point): Array<PixelPoint>
+}
+/** Cost-to-goal and next step for every hex of a grid, indexed by hex id. */
+export declare class FlowField {
+  /** Costs of every hex, `Infinity` for hexes that can not reach a goal. */
+  get costs(): Array<number>
+  getCost(hexId: number): number | null
+  /** The neighbor to step on from `hex_id`, `null` on goals and unreachable hexes. */
+  getNextHexId(hexId: number): number | null
+  /**
+   * Direction of the next hex: 0..5 for the cube offsets (1, 0, -1), (1, -1, 0), (0, -1, 1),
+   * (-1, 0, 1), (-1, 1, 0) and (0, 1, -1).
+   */
+  getDirection(hexId: number): number | null
+  /** Hex ids from `hex_id` to the goal it flows into, both included. */
+  getPath(hexId: number): Array<number>
+}
+export const enum PathStatus {
+  /** The path ends on a target hex */
+  Found = 0,
+  /** The search was cut short, the path ends on the explored hex closest to a target */
+  Partial = 1,
+  /** No target can be reached, the path is empty */
+  Unreachable = 2
+}
+/** How the target hexes of a `PathRequest` are reached. */
+export const enum PathTarget {
+  /** The path ends on one of the target hexes */
+  Hex = 0,
+  /** The path ends on a passable hex bordering the impassable area a target hex belongs to */
+  ImpassableAreaBorder = 1
+}
+export const enum SearchAlgorithm {
+  /** A* growing from the start hexes */
+  AStar = 0,
+  /**
+   * A* growing from the start and the target hexes at once, meeting in the middle.
+   * Explores fewer hexes on long routes.
+   */
+  Bidirectional = 1,
+  /**
+   * Hierarchical A* over the cluster abstraction of `HexGrid.buildHierarchy`, built with
+   * the default cluster size on first use. Fast on large maps, paths are near-optimal.
+   */
+  Hierarchical = 2
+}
+export interface PathRequest {
+  startIds: Array<number>
+  targetIds: Array<number>
+  /** Defaults to `PathTarget.Hex` */
+  target?: PathTarget
+}
+/**
+ * Which of the open hexes with the same estimated path cost a search expands first. Remaining
+ * ties go to the lower row, then the lower column, so the same grid and request always give
+ * the same path.
+ */
+export const enum TieBreak {
+  /** The hex that costs more to reach, which is the one with the lower heuristic */
+  HigherG = 0,
+  /** The hex that costs less to reach */
+  LowerG = 1,
+  /** Only the position of the hex */
+  Coordinates = 2
+}
+/**
+ * Limits of a search. When one of them stops the search before a target is reached, the
+ * result is a partial path to the explored hex closest to a target.
+ */
+export interface PathOptions {
+  /** Maximal number of hexes to expand */
+  maxExpandedNodes?: number
+  /** Maximal cost of the path */
+  maxCost?: number
+  /** Maximal number of steps of the path */
+  maxLength?: number
+  /** Defaults to `SearchAlgorithm.AStar` */
+  algorithm?: SearchAlgorithm
+  /** Per-unit cost rules, a hierarchical search falls back to A* when one is given */
+  profile?: MovementProfile
+  /**
+   * Straightens the path where a straight hex line costs no more and takes no more steps,
+   * removing the zig-zags between equally cheap routes (default: false)
+   */
+  smooth?: boolean
+  /** Defaults to `TieBreak.HigherG` */
+  tieBreak?: TieBreak
+}
+/** Straight run of the path, every step of it goes in the same direction */
+export interface PathSegment {
+  startIndex: number
+  endIndex: number
+  /** 0..5 as in `FlowField.getDirection` */
+  direction: number
+}
+export interface PathResult {
+  status: PathStatus
+  points: Array<Point>
+  hexIds: Array<number>
+  /** Cost accumulated on reaching every hex of the path, 0 for the start hex */
+  costs: Array<number>
+  totalCost: number
+  /** Number of hexes the search expanded */
+  expandedNodes: number
+  /** The path as straight runs, in order */
+  segments: Array<PathSegment>
+}
+/** Part of a waypoint path between two consecutive stops */
+export interface PathLeg {
+  /** Index of the first point of the leg in the path */
+  startIndex: number
+  /** Index of the last point of the leg, the first point of the next leg */
+  endIndex: number
+  cost: number
+}
+export interface WaypointPathResult {
+  path: PathResult
+  /**
+   * One leg per waypoint and one for the target, fewer when the path is partial and none
+   * when it is unreachable
+   */
+  legs: Array<PathLeg>
+}
+export const enum BattlegroundPolicy {
+  /** Battleground hexes cost `battlegroundMultiplier` times their usual cost */
+  Allow = 0,
+  /** Battleground hexes are never entered */
+  Forbid = 1
+}
+export interface SecurityRule {
+  securityIndex: SecurityIndexType
+  /** Factor applied to the cost of entering such hexes (default: 1) */
+  multiplier?: number
+  /** Such hexes are never entered (default: false) */
+  banned?: boolean
+}
+/**
+ * How a unit moves: entering a hex costs `multiplier / passability`, where the multiplier
+ * combines the rule of the hex's security index and the battleground multiplier. The start
+ * hex is never checked against the profile.
+ */
+export interface MovementProfile {
+  securityRules?: Array<SecurityRule>
+  /** Defaults to `BattlegroundPolicy.Allow` */
+  battleground?: BattlegroundPolicy
+  /** Default: 1 */
+  battlegroundMultiplier?: number
+  /** Hexes with a lower passability are treated as impassable */
+  minPassability?: number
+}
 export interface MapHexOptions {
   index: number
   x: number
@@ -17,11 +224,17 @@ export const enum SecurityIndexType {
   NotSafe = 2,
   FreePvP = 3
 }
+export const enum WrapMode {
+  None = 0,
+  /** Column `max_x` is adjacent to column `min_x` (cylindrical map) */
+  Horizontal = 1
+}
 export interface HexBase {
   x: number
   y: number
   passability: number
   battleground?: string
+  /** One of `no_battles`, `safe`, `not_safe` or `free_pvp` (default: `not_safe`) */
   securityIndex?: string
 }
 export interface Point {
@@ -43,20 +256,132 @@ export interface Hex {
   battleground: boolean
   securityIndex: string
 }
+export interface PathBetweenSets {
+  startId: number
+  targetId: number
+  path: Array<Point>
+}
+export interface ReachableHex {
+  hex: Hex
+  cost: number
+}
 export declare class HexGrid {
-  static new(gridBorder: GridBorder, hexes: Array<HexBase>): HexGrid
+  static new(gridBorder: GridBorder, hexes: Array<HexBase>, wrapMode?: WrapMode | undefined | null): HexGrid
+  /** Copies a rectangular region into a new independent grid. */
+  extract(border: GridBorder): HexGrid
+  /**
+   * Copies the hexes within `radius` of `center` into a new independent grid. The grid covers
+   * the bounding rectangle of the area, hexes outside the area are made impassable.
+   *
+   * On a wrapped grid the columns of an area crossing the seam stay next to each other, the
+   * hexes past the seam get an x below `minX` or above `maxX`. An area as wide as the grid
+   * covers all of its columns and wraps like it.
+   */
+  extractRange(center: Point, radius: number): HexGrid
+  /** Id of the hex in the grid this one was extracted from. */
+  getSourceHexId(hexId: number): number | null
   get hexes(): string
   get getwidth(): number
   get height(): number
   isInsideRadius(center: Point, target: Point, radius: number): boolean
   calculateDistanceBetweenHexes(pointA: Point, pointB: Point): number
+  pointToCube(point: Point): CubeCoord
+  cubeToPoint(cube: CubeCoord): Point
+  pointToAxial(point: Point): AxialCoord
+  axialToPoint(axial: AxialCoord): Point
   findHexByPosition(x: number, y: number): Hex | null
   getNeighboursById(hexId: number): Array<Hex>
   isHexOnBorderOfImpasableArea(point: Point): boolean
-  getHexesWithinRange(center: Point, range: number): Array<Hex>
+  getHexesWithinRange(center: Point, range: number, passableOnly?: boolean | undefined | null): Array<Hex>
+  getRing(center: Point, radius: number): Array<Hex>
+  getSpiral(center: Point, radius: number): Array<Hex>
+  getLine(pointA: Point, pointB: Point): Array<Hex>
+  hasLineOfSight(from: Point, to: Point, options?: LineOfSightOptions | undefined | null): LineOfSight
+  computeFov(origin: Point, radius: number): Array<Hex>
+  rotatePoint(point: Point, center: Point, steps: number): Point
+  rotatePoints(points: Array<Point>, center: Point, steps: number): Array<Point>
+  reflectPoint(point: Point, center: Point, axis: HexAxis): Point
+  reflectPoints(points: Array<Point>, center: Point, axis: HexAxis): Array<Point>
   getBorderPassableNeighbors(point: Point): Array<Hex>
+  /**
+   * Hexes entering and leaving the area of `getHexesWithinRange` when its center moves from
+   * `oldCenter` to `newCenter`, as `[added, deleted]` keyed by hex id. Both areas follow the
+   * same clipping and wrapping rules.
+   */
   getDifferenceBetweenAreasWithNeigborCenters(oldCenter: Point, newCenter: Point, range: number): Record<number, Hex>[]
-  buildPathToImpassableBorderHex(startId: number, targetId: number): Array<Point>
-  buildPathTowardsImpassableHex(startId: number, targetId: number): Array<Point>
-  buildPathToPassableHex(startId: number, targetId: number): Array<Point>
+  /**
+   * Changes the passability of a hex, a passability of 0 makes it impassable. Search caches
+   * and the hierarchy of `buildHierarchy` are updated around the hex only.
+   */
+  setHexPassability(hexId: number, passability: number): void
+  /**
+   * Splits the grid into square clusters of `clusterSize` hexes (default 16) and precomputes
+   * the abstract graph used by `SearchAlgorithm.Hierarchical`, replacing any previous one.
+   */
+  buildHierarchy(clusterSize?: number | undefined | null): void
+  /**
+   * Hexes reachable from `start_id` within `budget`, with the minimal cost of reaching each
+   * of them. Entering a hex costs `1 / passability`, as in path search.
+   */
+  getReachableHexes(startId: number, budget: number): Array<ReachableHex>
+  /**
+   * Integration field towards the closest of the goal hexes: the cost of reaching a goal from
+   * every hex and the neighbor to step on next, computed once for any number of agents.
+   */
+  computeFlowField(goalIds: Array<number>): FlowField
+  buildPathToImpassableBorderHex(startId: number, targetId: number, profile?: MovementProfile | undefined | null): Array<Point>
+  buildPathTowardsImpassableHex(startId: number, targetId: number, profile?: MovementProfile | undefined | null): Array<Point>
+  buildPathToPassableHex(startId: number, targetId: number, profile?: MovementProfile | undefined | null): Array<Point>
+  /**
+   * Cheapest path from any of the start hexes to any of the target hexes, together with the
+   * pair of hexes it connects.
+   */
+  buildPathBetweenSets(startIds: Array<number>, targetIds: Array<number>, profile?: MovementProfile | undefined | null): PathBetweenSets
+  /**
+   * Path search reporting the cost of every step and how much work the search took.
+   * An unreachable target is reported by the result status rather than an error.
+   */
+  buildPath(request: PathRequest, options?: PathOptions | undefined | null): PathResult
+  /**
+   * Path from the start hex through every waypoint in order to the target hex, each leg being
+   * the cheapest path between its stops. `legs` marks where every leg starts and ends in the
+   * path and what it costs, limits of `options` apply to every leg.
+   */
+  buildPathVia(startId: number, waypointIds: Array<number>, targetId: number, options?: PathOptions | undefined | null): WaypointPathResult
+  /**
+   * Up to `k` distinct paths between the hexes, the cheapest first. Every path found makes its
+   * hexes `1 + diversity` times more expensive for the following searches, so a higher
+   * diversity trades cost for routes that share fewer hexes. Costs in the results are the
+   * real ones, an unreachable target gives no paths. `k` must be between 1 and 16.
+   */
+  buildKPaths(startId: number, targetId: number, k: number, diversity: number, options?: PathOptions | undefined | null): Array<PathResult>
+  /**
+   * Solves many requests at once on all cores, returning the results in request order.
+   * Every request is validated before any search starts.
+   */
+  buildPathsBatch(requests: Array<PathRequest>, options?: PathOptions | undefined | null): Array<PathResult>
+  /**
+   * `buildPath` on the libuv thread pool. The search runs against the grid as it is when
+   * called, later changes don't affect it.
+   */
+  buildPathAsync(request: PathRequest, options?: PathOptions | undefined | null): Promise<PathResult>
+  /** `buildPathToPassableHex` on the libuv thread pool. */
+  buildPathToPassableHexAsync(startId: number, targetId: number, profile?: MovementProfile | undefined | null): Promise<Array<Point>>
+  /** `buildPathToImpassableBorderHex` on the libuv thread pool. */
+  buildPathToImpassableBorderHexAsync(startId: number, targetId: number, profile?: MovementProfile | undefined | null): Promise<Array<Point>>
+  /** `buildPathTowardsImpassableHex` on the libuv thread pool. */
+  buildPathTowardsImpassableHexAsync(startId: number, targetId: number, profile?: MovementProfile | undefined | null): Promise<Array<Point>>
+  /**
+   * Creates a persistent planner for a request with a single start hex and returns its id.
+   * The planner keeps its search state between queries and repairs it after
+   * `setHexPassability`, so re-pathing after a local change explores only around the change.
+   * At most 8192 planners can exist at once, `removePlanner` frees them.
+   */
+  createPlanner(request: PathRequest): number
+  /** Current path of a planner, `expandedNodes` counts the hexes its repair expanded. */
+  getPlannerPath(plannerId: number): PathResult
+  /** Moves the start of a planner, e.g. after the unit made a step along its path. */
+  movePlannerStart(plannerId: number, startId: number): void
+  /** Drops a planner, returns whether it existed. */
+  removePlanner(plannerId: number): boolean
 }
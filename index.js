@@ -310,7 +310,22 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { SecurityIndexType, HexGrid } = nativeBinding
+const { HexAxis, cubeAdd, cubeSubtract, cubeScale, cubeDistance, cubeToAxial, axialToCube, HexLayout, FlowField, PathStatus, PathTarget, SearchAlgorithm, TieBreak, BattlegroundPolicy, SecurityIndexType, WrapMode, HexGrid } = nativeBinding
 
+module.exports.HexAxis = HexAxis
+module.exports.cubeAdd = cubeAdd
+module.exports.cubeSubtract = cubeSubtract
+module.exports.cubeScale = cubeScale
+module.exports.cubeDistance = cubeDistance
+module.exports.cubeToAxial = cubeToAxial
+module.exports.axialToCube = axialToCube
+module.exports.HexLayout = HexLayout
+module.exports.FlowField = FlowField
+module.exports.PathStatus = PathStatus
+module.exports.PathTarget = PathTarget
+module.exports.SearchAlgorithm = SearchAlgorithm
+module.exports.TieBreak = TieBreak
+module.exports.BattlegroundPolicy = BattlegroundPolicy
 module.exports.SecurityIndexType = SecurityIndexType
+module.exports.WrapMode = WrapMode
 module.exports.HexGrid = HexGrid
@@ -0,0 +1,49 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::path::{PathOptions, PathStatus, SearchEndpoints, SearchOutcome};
+use crate::search_view::SearchView;
+
+// Paths one request can ask for, every one of them costs up to two searches
+pub const MAX_PATHS: usize = 16;
+
+/// Up to `k` distinct paths by penalty rerouting: after every search the cost of entering the
+/// hexes of the path found is multiplied by `1 + diversity`, so the next search is pushed away
+/// from the corridors already taken. Gives up after `2 * k` searches, returns only complete
+/// paths, cheapest first by their unpenalized cost. `k` is capped at `MAX_PATHS`.
+pub fn search_alternatives(
+    view: &SearchView,
+    endpoints: &SearchEndpoints,
+    options: &PathOptions,
+    k: usize,
+    diversity: f64,
+) -> Vec<SearchOutcome> {
+    let k = k.min(MAX_PATHS);
+    let mut penalized_view = view.clone();
+    let mut seen: HashSet<Vec<(usize, usize)>> = HashSet::new();
+    let mut paths = Vec::with_capacity(k);
+
+    for _ in 0..2 * k {
+        if paths.len() == k {
+            break;
+        }
+
+        let outcome = penalized_view.search(endpoints, options);
+        if outcome.status != PathStatus::Found {
+            break;
+        }
+
+        // The search is done with the view, so the penalties are updated in place
+        let penalties = Arc::make_mut(penalized_view.penalties.get_or_insert_with(Default::default));
+        for &point in &outcome.path {
+            *penalties.entry(point).or_insert(1.0) *= 1.0 + diversity;
+        }
+        if seen.insert(outcome.path.clone()) {
+            paths.push(outcome);
+        }
+    }
+
+    let path_cost = |outcome: &SearchOutcome| outcome.path.iter().skip(1).map(|&(x, y)| view.step_cost(x, y)).sum::<f64>();
+    paths.sort_by(|a, b| path_cost(a).total_cmp(&path_cost(b)));
+    paths
+}
@@ -0,0 +1,138 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::heap::CustomHeap;
+use crate::path::{PathOptions, PathStatus, SearchOutcome, TieBreak};
+use crate::temp_search_grid::TempSearchGrid;
+
+// Search state of an opened node, kept apart from the grid so searches only need to read it
+#[derive(Debug, Clone, Copy)]
+struct NodeState {
+    g: f64,
+    h: f64,
+    closed: bool,
+    parent: Option<(usize, usize)>,
+    steps: usize,
+}
+
+/// A* over the passable neighbor cache, growing from every start node at once until one of the
+/// terminal nodes is expanded. `step_cost` is the cost of entering a node, nodes with an
+/// infinite one are never entered. `heuristic` estimates the cost to the closest terminal node.
+///
+/// When a limit of `options` stops the search first, the path ends on the expanded node with
+/// the lowest heuristic. Only opened nodes get a state, so a search costs time and memory in
+/// proportion to the region it explores rather than to the grid.
+pub fn search<C, H>(
+    grid: &TempSearchGrid,
+    start_points: &[(usize, usize)],
+    terminal_points: &[(usize, usize)],
+    options: &PathOptions,
+    step_cost: C,
+    heuristic: H,
+) -> SearchOutcome
+where
+    C: Fn(usize, usize) -> f64,
+    H: Fn(usize, usize) -> f64,
+{
+    let max_expanded_nodes = options.max_expanded_nodes.unwrap_or(u32::MAX);
+    let max_cost = options.max_cost.unwrap_or(f64::INFINITY);
+    let max_length = options.max_length.map_or(usize::MAX, |length| length as usize);
+    // Whether a limit cut the search short, and the expanded node closest to a target
+    // as (h, g, point) for a partial path
+    let mut limited = false;
+    let mut closest: Option<(f64, f64, (usize, usize))> = None;
+
+    let mut states: HashMap<(usize, usize), NodeState> = HashMap::new();
+    let mut expanded_nodes = 0;
+    let mut open_list = CustomHeap::new(start_points.len(), options.tie_break.unwrap_or(TieBreak::HigherG));
+    let terminal_node_set: HashSet<(usize, usize)> = terminal_points.iter().copied().collect();
+
+    // Every start node begins with zero cost, the search grows from all of them at once. The
+    // start nodes count as explored, a search stopped before any expansion ends on one of them
+    for &(start_x, start_y) in start_points {
+        let h = heuristic(start_x, start_y);
+        states.insert((start_x, start_y), NodeState { g: 0.0, h, closed: false, parent: None, steps: 0 });
+        open_list.push((0.0, 0.0, start_x, start_y));
+        if closest.is_none_or(|(closest_h, _, _)| h < closest_h) {
+            closest = Some((h, 0.0, (start_x, start_y)));
+        }
+    }
+
+    while let Some((x, y)) = open_list.pop() {
+        let is_terminal = terminal_node_set.contains(&(x, y));
+
+        if !is_terminal && expanded_nodes >= max_expanded_nodes {
+            limited = true;
+            break;
+        }
+
+        let state = states.get_mut(&(x, y)).unwrap();
+        state.closed = true;
+        expanded_nodes += 1;
+        let current = *state;
+
+        if is_terminal {
+            let path = backtrace(&states, (x, y));
+            return SearchOutcome { status: PathStatus::Found, path, expanded_nodes };
+        }
+
+        if closest.is_none_or(|(h, g, _)| current.h < h || (current.h == h && current.g < g)) {
+            closest = Some((current.h, current.g, (x, y)));
+        }
+
+        for neighbor in grid.get_passable_neighbors(x, y) {
+            let point = (neighbor.x, neighbor.y);
+            if states.get(&point).is_some_and(|state| state.closed) {
+                continue;
+            }
+
+            let cost = step_cost(neighbor.x, neighbor.y);
+            if cost == f64::INFINITY {
+                continue;
+            }
+
+            // Calculate `g` score (cost to get to this neighbor)
+            let ng = current.g + cost;
+            if ng > max_cost || current.steps + 1 > max_length {
+                limited = true;
+                continue;
+            }
+
+            match states.get_mut(&point) {
+                None => {
+                    let h = heuristic(neighbor.x, neighbor.y);
+                    states.insert(point, NodeState { g: ng, h, closed: false, parent: Some((x, y)), steps: current.steps + 1 });
+                    open_list.push((ng + h, ng, neighbor.x, neighbor.y));
+                }
+                Some(state) if ng < state.g => {
+                    state.g = ng;
+                    state.parent = Some((x, y));
+                    state.steps = current.steps + 1;
+                    open_list.update((ng + state.h, ng, neighbor.x, neighbor.y));
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    match closest {
+        Some((_, _, point)) if limited => SearchOutcome {
+            status: PathStatus::Partial,
+            path: backtrace(&states, point),
+            expanded_nodes,
+        },
+        _ => SearchOutcome { status: PathStatus::Unreachable, path: Vec::new(), expanded_nodes },
+    }
+}
+
+// Node positions from a start node to `point`
+fn backtrace(states: &HashMap<(usize, usize), NodeState>, point: (usize, usize)) -> Vec<(usize, usize)> {
+    let mut path = vec![point];
+    let mut current = states[&point].parent;
+    while let Some(parent) = current {
+        path.push(parent);
+        current = states[&parent].parent;
+    }
+
+    path.reverse();
+    path
+}
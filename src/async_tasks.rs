@@ -0,0 +1,66 @@
+use napi::bindgen_prelude::*;
+use napi::{Env, Task};
+use napi_derive::napi;
+
+use crate::path::{path_not_found_message, PathOptions, PathResult, PathStatus, SearchEndpoints, SearchOutcome};
+use crate::search_view::SearchView;
+use crate::Point;
+
+/// Path search run on the libuv thread pool against a snapshot of the grid.
+pub struct PathTask {
+    view: SearchView,
+    endpoints: SearchEndpoints,
+    options: PathOptions,
+}
+
+impl PathTask {
+    pub fn new(view: SearchView, endpoints: SearchEndpoints, options: PathOptions) -> Self {
+        PathTask { view, endpoints, options }
+    }
+}
+
+#[napi]
+impl Task for PathTask {
+    type Output = SearchOutcome;
+    type JsValue = PathResult;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        Ok(self.view.search(&self.endpoints, &self.options))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(self.view.build_result(output))
+    }
+}
+
+/// Like `PathTask`, but resolves with the points of the path and rejects when no target can be
+/// reached, as the synchronous `build_path_*` methods do.
+pub struct PointPathTask(PathTask);
+
+impl PointPathTask {
+    pub fn new(view: SearchView, endpoints: SearchEndpoints) -> Self {
+        PointPathTask(PathTask::new(view, endpoints, PathOptions::default()))
+    }
+}
+
+#[napi]
+impl Task for PointPathTask {
+    type Output = SearchOutcome;
+    type JsValue = Vec<Point>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        self.0.compute()
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        if output.status != PathStatus::Found {
+            let endpoints = &self.0.endpoints;
+            return Err(Error::new(
+                Status::InvalidArg,
+                path_not_found_message(&endpoints.start_points, &endpoints.target_points),
+            ));
+        }
+
+        Ok(output.path.into_iter().map(|(x, y)| self.0.view.to_point(x, y)).collect())
+    }
+}
@@ -0,0 +1,38 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::path::{PathOptions, SearchEndpoints, SearchOutcome};
+use crate::search_view::SearchView;
+
+/// Runs one search per entry of `requests` on as many threads as there are cores and returns
+/// the outcomes in request order. Threads pick the next unsolved request as they finish, so a
+/// few long searches don't hold back the rest of the batch.
+pub fn search_all(view: &SearchView, requests: &[SearchEndpoints], options: &PathOptions) -> Vec<SearchOutcome> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get()).min(requests.len());
+    if threads <= 1 {
+        return requests.iter().map(|endpoints| view.search(endpoints, options)).collect();
+    }
+
+    let next_request = AtomicUsize::new(0);
+    let mut solved: Vec<(usize, SearchOutcome)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut outcomes = Vec::new();
+                    loop {
+                        let index = next_request.fetch_add(1, Ordering::Relaxed);
+                        match requests.get(index) {
+                            Some(endpoints) => outcomes.push((index, view.search(endpoints, options))),
+                            None => return outcomes,
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect()
+    });
+
+    solved.sort_unstable_by_key(|&(index, _)| index);
+    solved.into_iter().map(|(_, outcome)| outcome).collect()
+}
@@ -0,0 +1,270 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::heap::CustomHeap;
+use crate::path::{PathOptions, PathStatus, SearchOutcome, TieBreak};
+use crate::temp_search_grid::TempSearchGrid;
+
+// Cost, step count and parent of a node reached by one direction
+#[derive(Debug, Clone, Copy)]
+struct Visit {
+    g: f64,
+    steps: usize,
+    parent: Option<(usize, usize)>,
+}
+
+// Search state of one direction, holding only the nodes it reached
+struct Frontier {
+    open_list: CustomHeap,
+    visits: HashMap<(usize, usize), Visit>,
+    closed: HashSet<(usize, usize)>,
+}
+
+impl Frontier {
+    fn new(capacity: usize, tie_break: TieBreak) -> Self {
+        Frontier {
+            open_list: CustomHeap::new(capacity, tie_break),
+            visits: HashMap::new(),
+            closed: HashSet::new(),
+        }
+    }
+
+    fn g(&self, point: (usize, usize)) -> f64 {
+        self.visits.get(&point).map_or(f64::INFINITY, |visit| visit.g)
+    }
+
+    // Node positions from `point` back to the node this direction started from
+    fn backtrace(&self, point: (usize, usize)) -> Vec<(usize, usize)> {
+        let mut path = vec![point];
+        let mut current = self.visits[&point].parent;
+        while let Some(parent) = current {
+            path.push(parent);
+            current = self.visits[&parent].parent;
+        }
+        path
+    }
+}
+
+/// Bidirectional A*: grows one search from the start nodes and one from the terminal nodes over
+/// the passable neighbor cache, whose links are symmetric, and joins them where they meet.
+///
+/// Moving onto a node costs its `step_cost`, so the backward search pays for the node it leaves.
+/// Nodes with an infinite cost are never entered. `forward_h` estimates the cost to the closest terminal and `backward_h` the
+/// cost to the closest start. The search stops once the cheapest open node of either direction
+/// cannot improve on the best meeting found so far. Limits of `options` apply as in the
+/// one-directional search, a partial path ends on the forward node closest to a terminal.
+/// When `max_expanded_nodes` stops the search after the directions met, the best path found
+/// is returned as partial: it reaches a terminal, but a cheaper one may exist.
+pub fn search<C, F, B>(
+    grid: &TempSearchGrid,
+    start_points: &[(usize, usize)],
+    terminal_points: &[(usize, usize)],
+    options: &PathOptions,
+    step_cost: C,
+    forward_h: F,
+    backward_h: B,
+) -> SearchOutcome
+where
+    C: Fn(usize, usize) -> f64,
+    F: Fn(usize, usize) -> f64,
+    B: Fn(usize, usize) -> f64,
+{
+    let max_expanded_nodes = options.max_expanded_nodes.unwrap_or(u32::MAX);
+    let max_cost = options.max_cost.unwrap_or(f64::INFINITY);
+    let max_length = options.max_length.map_or(usize::MAX, |length| length as usize);
+    let mut limited = false;
+    // Set when the expansion limit stopped the search before the best path was proven
+    let mut capped = false;
+    let mut closest: Option<(f64, f64, (usize, usize))> = None;
+
+    let tie_break = options.tie_break.unwrap_or(TieBreak::HigherG);
+    let mut forward = Frontier::new(start_points.len(), tie_break);
+    let mut backward = Frontier::new(terminal_points.len(), tie_break);
+    // Cheapest known path as (cost, meeting node)
+    let mut best: Option<(f64, (usize, usize))> = None;
+    let mut expanded_nodes = 0;
+
+    // The start nodes count as explored, a search stopped before any expansion ends on one of them
+    for &(x, y) in start_points {
+        let h = forward_h(x, y);
+        forward.visits.insert((x, y), Visit { g: 0.0, steps: 0, parent: None });
+        forward.open_list.push((h, 0.0, x, y));
+        if closest.is_none_or(|(closest_h, _, _)| h < closest_h) {
+            closest = Some((h, 0.0, (x, y)));
+        }
+    }
+    for &(x, y) in terminal_points {
+        backward.visits.insert((x, y), Visit { g: 0.0, steps: 0, parent: None });
+        backward.open_list.push((backward_h(x, y), 0.0, x, y));
+        if forward.g((x, y)) == 0.0 {
+            best = Some((0.0, (x, y)));
+        }
+    }
+
+    // Once one direction explored everything it can reach, every meeting has been recorded
+    while let (Some(forward_top), Some(backward_top)) = (forward.open_list.peek_cost(), backward.open_list.peek_cost()) {
+        if let Some((best_cost, _)) = best {
+            if forward_top >= best_cost || backward_top >= best_cost {
+                break;
+            }
+        }
+
+        // Expanding the smaller frontier keeps both of them balanced
+        let is_forward = forward.open_list.len() <= backward.open_list.len();
+        let (current, other) = if is_forward {
+            (&mut forward, &backward)
+        } else {
+            (&mut backward, &forward)
+        };
+
+        let (x, y) = current.open_list.pop().unwrap();
+        // Stale entry of a node that was pushed again with a lower cost
+        if current.closed.contains(&(x, y)) {
+            continue;
+        }
+        if expanded_nodes >= max_expanded_nodes {
+            limited = true;
+            capped = true;
+            break;
+        }
+        current.closed.insert((x, y));
+        expanded_nodes += 1;
+
+        let Visit { g: current_g, steps: current_steps, .. } = current.visits[&(x, y)];
+        if is_forward {
+            let h = forward_h(x, y);
+            if closest.is_none_or(|(closest_h, closest_g, _)| h < closest_h || (h == closest_h && current_g < closest_g)) {
+                closest = Some((h, current_g, (x, y)));
+            }
+        }
+
+        let leaving_cost = step_cost(x, y);
+        let neighbors = grid.get_passable_neighbors(x, y);
+
+        for neighbor in neighbors {
+            let point = (neighbor.x, neighbor.y);
+            if current.closed.contains(&point) {
+                continue;
+            }
+
+            let cost = if is_forward { step_cost(neighbor.x, neighbor.y) } else { leaving_cost };
+            if cost == f64::INFINITY {
+                continue;
+            }
+            let ng = current_g + cost;
+            if ng > max_cost || current_steps + 1 > max_length {
+                limited = true;
+                continue;
+            }
+            if ng >= current.g(point) {
+                continue;
+            }
+
+            current.visits.insert(point, Visit { g: ng, steps: current_steps + 1, parent: Some((x, y)) });
+            let h = if is_forward { forward_h(neighbor.x, neighbor.y) } else { backward_h(neighbor.x, neighbor.y) };
+            current.open_list.push((ng + h, ng, neighbor.x, neighbor.y));
+
+            let Some(other_visit) = other.visits.get(&point) else {
+                continue;
+            };
+            let meeting_cost = ng + other_visit.g;
+            if best.is_none_or(|(best_cost, _)| meeting_cost < best_cost) {
+                if meeting_cost > max_cost || current_steps + 1 + other_visit.steps > max_length {
+                    limited = true;
+                } else {
+                    best = Some((meeting_cost, point));
+                }
+            }
+        }
+    }
+
+    if let Some((_, meeting)) = best {
+        let mut path = forward.backtrace(meeting);
+        path.reverse();
+        path.extend(backward.backtrace(meeting).into_iter().skip(1));
+        let status = if capped { PathStatus::Partial } else { PathStatus::Found };
+        return SearchOutcome { status, path, expanded_nodes };
+    }
+
+    match closest {
+        Some((_, _, point)) if limited => {
+            let mut path = forward.backtrace(point);
+            path.reverse();
+            SearchOutcome { status: PathStatus::Partial, path, expanded_nodes }
+        }
+        _ => SearchOutcome { status: PathStatus::Unreachable, path: Vec::new(), expanded_nodes },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_grid::{assert_cost_eq, Lcg, RandomGrid};
+
+    #[test]
+    fn costs_match_astar() {
+        let mut rng = Lcg::new(29);
+        for case in 0..60 {
+            let grid = RandomGrid::new(&mut rng);
+            for query in 0..10 {
+                let starts: Vec<(usize, usize)> = (0..1 + rng.below(2)).filter_map(|_| grid.random_passable_node(&mut rng)).collect();
+                let terminals: Vec<(usize, usize)> = (0..1 + rng.below(3)).filter_map(|_| grid.random_passable_node(&mut rng)).collect();
+                if starts.is_empty() || terminals.is_empty() {
+                    continue;
+                }
+
+                let outcome = search(
+                    &grid.grid,
+                    &starts,
+                    &terminals,
+                    &PathOptions::default(),
+                    |x, y| grid.step_cost(x, y),
+                    |x, y| grid.heuristic(x, y, &terminals),
+                    |x, y| grid.heuristic(x, y, &starts),
+                );
+                let context = format!("case {} query {}", case, query);
+                let cost = (outcome.status == PathStatus::Found).then(|| grid.path_cost(&outcome.path));
+                assert_cost_eq(cost, grid.astar_cost(&starts, &terminals), &context);
+
+                // The joined halves form one walk from a start to a terminal
+                if cost.is_some() {
+                    assert!(starts.contains(&outcome.path[0]), "{}", context);
+                    assert!(terminals.contains(&outcome.path[outcome.path.len() - 1]), "{}", context);
+                    for step in outcome.path.windows(2) {
+                        let neighbors = grid.grid.get_passable_neighbors(step[0].0, step[0].1);
+                        assert!(neighbors.iter().any(|node| (node.x, node.y) == step[1]), "{}", context);
+                    }
+                }
+            }
+        }
+    }
+    #[test]
+    fn capped_searches_report_only_proven_paths_as_found() {
+        let mut rng = Lcg::new(7);
+        for case in 0..60 {
+            let grid = RandomGrid::new(&mut rng);
+            let (Some(start), Some(terminal)) = (grid.random_passable_node(&mut rng), grid.random_passable_node(&mut rng)) else {
+                continue;
+            };
+            let expected = grid.astar_cost(&[start], &[terminal]);
+
+            for max_expanded_nodes in 1..40 {
+                let options = PathOptions { max_expanded_nodes: Some(max_expanded_nodes), ..PathOptions::default() };
+                let outcome = search(
+                    &grid.grid,
+                    &[start],
+                    &[terminal],
+                    &options,
+                    |x, y| grid.step_cost(x, y),
+                    |x, y| grid.heuristic(x, y, &[terminal]),
+                    |x, y| grid.heuristic(x, y, &[start]),
+                );
+                let context = format!("case {} limit {}", case, max_expanded_nodes);
+                match outcome.status {
+                    PathStatus::Found => assert_cost_eq(Some(grid.path_cost(&outcome.path)), expected, &context),
+                    PathStatus::Partial => assert_eq!(outcome.path[0], start, "{}", context),
+                    PathStatus::Unreachable => assert_eq!(expected, None, "{}", context),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use std::ops::{Add, Mul, Sub};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde::Serialize;
+
+/// Cube coordinates of a hex. The invariant `q + r + s == 0` always holds, functions taking
+/// cube coordinates reject ones that break it.
+#[napi(object)]
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CubeCoord {
+    pub q: i32,
+    pub r: i32,
+    pub s: i32,
+}
+
+/// Axial coordinates of a hex (cube coordinates without the redundant `s`).
+#[napi(object)]
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AxialCoord {
+    pub q: i32,
+    pub r: i32,
+}
+
+/// Axes of a hex grid, named after the cube coordinate that stays fixed when reflecting across them.
+#[napi]
+#[derive(Debug)]
+pub enum HexAxis {
+    Q,
+    R,
+    S,
+}
+
+// Neighbor directions, in the order ring walks turn through them.
+pub const CUBE_DIRECTIONS: [CubeCoord; 6] = [
+    CubeCoord { q: 1, r: 0, s: -1 },
+    CubeCoord { q: 1, r: -1, s: 0 },
+    CubeCoord { q: 0, r: -1, s: 1 },
+    CubeCoord { q: -1, r: 0, s: 1 },
+    CubeCoord { q: -1, r: 1, s: 0 },
+    CubeCoord { q: 0, r: 1, s: -1 },
+];
+
+impl CubeCoord {
+    pub fn new(q: i32, r: i32) -> Self {
+        CubeCoord { q, r, s: -q - r }
+    }
+
+    /// Checks the invariant of coordinates passed in from JavaScript.
+    pub fn validate(self) -> Result<Self, String> {
+        if self.q as i64 + self.r as i64 + self.s as i64 != 0 {
+            return Err(Error::new(
+                Status::InvalidArg.to_string(),
+                format!("Cube coordinates must sum to 0, got ({}, {}, {})", self.q, self.r, self.s),
+            ));
+        }
+        Ok(self)
+    }
+
+    pub fn from_axial(axial: AxialCoord) -> Self {
+        Self::new(axial.q, axial.r)
+    }
+
+    pub fn to_axial(self) -> AxialCoord {
+        AxialCoord { q: self.q, r: self.r }
+    }
+
+    /// Converts a column-offset position into cube coordinates.
+    ///
+    /// `parity` tells which columns are shoved down: with `0` the odd columns are
+    /// (odd-q), with `1` the even ones are (even-q). World coordinates of a `HexGrid`
+    /// are always odd-q, node coordinates of the search grid use the grid's odd increment.
+    /// `div_euclid` keeps the conversion exact for negative columns.
+    pub fn from_offset(col: i32, row: i32, parity: i32) -> Self {
+        Self::new(col, row - (col + parity).div_euclid(2))
+    }
+
+    pub fn to_offset(self, parity: i32) -> (i32, i32) {
+        (self.q, self.r + (self.q + parity).div_euclid(2))
+    }
+
+    pub fn length(self) -> i32 {
+        (self.q.abs() + self.r.abs() + self.s.abs()) / 2
+    }
+
+    pub fn distance(self, other: CubeCoord) -> i32 {
+        (self - other).length()
+    }
+
+    pub fn direction(direction: usize) -> Self {
+        CUBE_DIRECTIONS[direction % 6]
+    }
+
+    pub fn neighbor(self, direction: usize) -> Self {
+        self + Self::direction(direction)
+    }
+
+    /// Rotates around the origin by `steps` * 60°. A positive step turns direction `i` into
+    /// direction `i + 1`, which is counter-clockwise on screen.
+    pub fn rotate(self, steps: i32) -> Self {
+        match steps.rem_euclid(6) {
+            0 => self,
+            1 => CubeCoord { q: -self.s, r: -self.q, s: -self.r },
+            2 => CubeCoord { q: self.r, r: self.s, s: self.q },
+            3 => CubeCoord { q: -self.q, r: -self.r, s: -self.s },
+            4 => CubeCoord { q: self.s, r: self.q, s: self.r },
+            _ => CubeCoord { q: -self.r, r: -self.s, s: -self.q },
+        }
+    }
+
+    pub fn rotate_around(self, center: CubeCoord, steps: i32) -> Self {
+        (self - center).rotate(steps) + center
+    }
+
+    /// Mirrors across the given axis through the origin.
+    pub fn reflect(self, axis: HexAxis) -> Self {
+        match axis {
+            HexAxis::Q => CubeCoord { q: self.q, r: self.s, s: self.r },
+            HexAxis::R => CubeCoord { q: self.s, r: self.r, s: self.q },
+            HexAxis::S => CubeCoord { q: self.r, r: self.q, s: self.s },
+        }
+    }
+
+    pub fn reflect_around(self, center: CubeCoord, axis: HexAxis) -> Self {
+        (self - center).reflect(axis) + center
+    }
+
+    /// Hexes at exactly `radius` steps, walked in direction order starting from the corner
+    /// `radius` steps along `CUBE_DIRECTIONS[4]`.
+    pub fn ring(self, radius: u32) -> Vec<CubeCoord> {
+        if radius == 0 {
+            return vec![self];
+        }
+
+        let mut result = Vec::with_capacity(6 * radius as usize);
+        let mut cube = self + Self::direction(4) * radius as i32;
+        for direction in 0..6 {
+            for _ in 0..radius {
+                result.push(cube);
+                cube = cube.neighbor(direction);
+            }
+        }
+        result
+    }
+
+    /// Rounds fractional cube coordinates to the hex containing them, restoring `q + r + s == 0`
+    /// by recomputing the component that moved the most.
+    pub fn round(q: f64, r: f64, s: f64) -> Self {
+        let (mut rq, mut rr, mut rs) = (q.round(), r.round(), s.round());
+        let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+
+        if dq > dr && dq > ds {
+            rq = -rr - rs;
+        } else if dr > ds {
+            rr = -rq - rs;
+        } else {
+            rs = -rq - rr;
+        }
+        CubeCoord { q: rq as i32, r: rr as i32, s: rs as i32 }
+    }
+
+    /// Hexes a straight line from `self` to `other` passes through, both ends included.
+    ///
+    /// The start is nudged by a tiny epsilon so that points falling exactly on a hex edge
+    /// are always rounded to the same side.
+    pub fn line_to(self, other: CubeCoord) -> Vec<CubeCoord> {
+        let n = self.distance(other);
+        if n == 0 {
+            return vec![self];
+        }
+
+        let (aq, ar, a_s) = (self.q as f64 + 1e-6, self.r as f64 + 2e-6, self.s as f64 - 3e-6);
+        let (bq, br, bs) = (other.q as f64, other.r as f64, other.s as f64);
+
+        (0..=n)
+            .map(|i| {
+                let t = i as f64 / n as f64;
+                Self::round(aq + (bq - aq) * t, ar + (br - ar) * t, a_s + (bs - a_s) * t)
+            })
+            .collect()
+    }
+
+    /// The center followed by every ring up to `radius`, innermost first.
+    pub fn spiral(self, radius: u32) -> Vec<CubeCoord> {
+        (0..=radius).flat_map(|ring| self.ring(ring)).collect()
+    }
+}
+
+impl Add for CubeCoord {
+    type Output = CubeCoord;
+
+    fn add(self, other: CubeCoord) -> CubeCoord {
+        CubeCoord { q: self.q + other.q, r: self.r + other.r, s: self.s + other.s }
+    }
+}
+
+impl Sub for CubeCoord {
+    type Output = CubeCoord;
+
+    fn sub(self, other: CubeCoord) -> CubeCoord {
+        CubeCoord { q: self.q - other.q, r: self.r - other.r, s: self.s - other.s }
+    }
+}
+
+impl Mul<i32> for CubeCoord {
+    type Output = CubeCoord;
+
+    fn mul(self, factor: i32) -> CubeCoord {
+        CubeCoord { q: self.q * factor, r: self.r * factor, s: self.s * factor }
+    }
+}
+
+#[napi]
+pub fn cube_add(a: CubeCoord, b: CubeCoord) -> Result<CubeCoord, String> {
+    Ok(a.validate()? + b.validate()?)
+}
+
+#[napi]
+pub fn cube_subtract(a: CubeCoord, b: CubeCoord) -> Result<CubeCoord, String> {
+    Ok(a.validate()? - b.validate()?)
+}
+
+#[napi]
+pub fn cube_scale(cube: CubeCoord, factor: i32) -> Result<CubeCoord, String> {
+    Ok(cube.validate()? * factor)
+}
+
+#[napi]
+pub fn cube_distance(a: CubeCoord, b: CubeCoord) -> Result<i32, String> {
+    Ok(a.validate()?.distance(b.validate()?))
+}
+
+#[napi]
+pub fn cube_to_axial(cube: CubeCoord) -> Result<AxialCoord, String> {
+    Ok(cube.validate()?.to_axial())
+}
+
+#[napi]
+pub fn axial_to_cube(axial: AxialCoord) -> CubeCoord {
+    CubeCoord::from_axial(axial)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_offset_handles_negative_columns() {
+        // Odd-q: column -1 is odd and shoved down like column 1
+        assert_eq!(CubeCoord::from_offset(-1, 0, 0), CubeCoord::new(-1, 1));
+        assert_eq!(CubeCoord::from_offset(-2, 1, 0), CubeCoord::new(-2, 2));
+        assert_eq!(CubeCoord::from_offset(-3, -2, 0), CubeCoord::new(-3, 0));
+        // Even-q: column -1 is not shoved, column -2 is
+        assert_eq!(CubeCoord::from_offset(-1, 0, 1), CubeCoord::new(-1, 0));
+        assert_eq!(CubeCoord::from_offset(-2, 1, 1), CubeCoord::new(-2, 2));
+    }
+
+    #[test]
+    fn offset_round_trip_across_zero() {
+        for parity in 0..2 {
+            for col in -7..7 {
+                for row in -4..4 {
+                    assert_eq!(CubeCoord::from_offset(col, row, parity).to_offset(parity), (col, row));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn negative_columns_stay_adjacent() {
+        // Odd-q neighbors of (-1, 0), a shoved down column
+        let center = CubeCoord::from_offset(-1, 0, 0);
+        for (col, row) in [(-1, -1), (-1, 1), (0, 0), (0, 1), (-2, 0), (-2, 1)] {
+            assert_eq!(center.distance(CubeCoord::from_offset(col, row, 0)), 1, "({}, {})", col, row);
+        }
+        assert_eq!(center.distance(CubeCoord::from_offset(0, -1, 0)), 2);
+    }
+
+    #[test]
+    fn cube_functions_reject_broken_invariant() {
+        let broken = CubeCoord { q: 1, r: 0, s: 0 };
+        let valid = CubeCoord::new(1, -1);
+        assert!(cube_distance(broken, valid).is_err());
+        assert!(cube_add(valid, broken).is_err());
+        assert!(cube_to_axial(broken).is_err());
+        assert_eq!(cube_distance(valid, CubeCoord::new(-1, 2)).unwrap(), 3);
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use crate::heap::CustomHeap;
+use crate::path::TieBreak;
+use crate::temp_node::TempNode;
+use crate::temp_search_grid::TempSearchGrid;
+
+type NodePoint = (usize, usize);
+
+// Cost of a reached node, the node it was reached from and whether it is settled
+#[derive(Debug, Clone, Copy)]
+struct NodeState {
+    cost: f64,
+    parent: Option<NodePoint>,
+    closed: bool,
+}
+
+// State of the reached nodes. An unbounded expansion covers the whole grid, a table indexed by
+// `y * width + x` is cheaper for it. A budget keeps the expansion local, a map keeps it from
+// touching the rest of the grid.
+enum NodeStates {
+    Dense { width: usize, states: Vec<Option<NodeState>> },
+    Sparse(HashMap<NodePoint, NodeState>),
+}
+
+impl NodeStates {
+    fn get(&self, (x, y): NodePoint) -> Option<&NodeState> {
+        match self {
+            NodeStates::Dense { width, states } => states[y * width + x].as_ref(),
+            NodeStates::Sparse(states) => states.get(&(x, y)),
+        }
+    }
+
+    fn get_mut(&mut self, (x, y): NodePoint) -> Option<&mut NodeState> {
+        match self {
+            NodeStates::Dense { width, states } => states[y * *width + x].as_mut(),
+            NodeStates::Sparse(states) => states.get_mut(&(x, y)),
+        }
+    }
+
+    fn insert(&mut self, (x, y): NodePoint, state: NodeState) {
+        match self {
+            NodeStates::Dense { width, states } => states[y * *width + x] = Some(state),
+            NodeStates::Sparse(states) => {
+                states.insert((x, y), state);
+            }
+        }
+    }
+}
+
+/// Minimal costs (and the node each one was reached from) produced by a Dijkstra expansion.
+pub struct CostMap {
+    states: NodeStates,
+}
+
+impl CostMap {
+    pub fn get_parent(&self, x: usize, y: usize) -> Option<NodePoint> {
+        self.states.get((x, y)).and_then(|state| state.parent)
+    }
+
+    /// Reached nodes in row-major order together with their costs.
+    pub fn iter_reached(&self) -> impl Iterator<Item = (NodePoint, f64)> {
+        let reached: Vec<(NodePoint, f64)> = match &self.states {
+            NodeStates::Dense { width, states } => states
+                .iter()
+                .enumerate()
+                .filter_map(|(index, state)| state.map(|state| ((index % width, index / width), state.cost)))
+                .collect(),
+            NodeStates::Sparse(states) => {
+                let mut reached: Vec<(NodePoint, f64)> = states.iter().map(|(&point, state)| (point, state.cost)).collect();
+                reached.sort_unstable_by_key(|&((x, y), _)| (y, x));
+                reached
+            }
+        };
+        reached.into_iter()
+    }
+}
+
+/// Dijkstra expansion over the passable neighbor cache, starting at cost 0 from every source.
+///
+/// `step_cost(current, neighbor)` is the cost of the edge between a settled node and one of its
+/// neighbors. Nodes whose cost would exceed `max_cost` are not reached. With a finite `max_cost`
+/// only the reached nodes are stored, so a small budget stays cheap on a large grid.
+pub fn expand<F>(grid: &TempSearchGrid, sources: &[NodePoint], max_cost: f64, step_cost: F) -> CostMap
+where
+    F: Fn(&TempNode, &TempNode) -> f64,
+{
+    let mut states = if max_cost.is_finite() {
+        NodeStates::Sparse(HashMap::new())
+    } else {
+        NodeStates::Dense { width: grid.get_width(), states: vec![None; grid.get_width() * grid.get_height()] }
+    };
+    let mut open_list = CustomHeap::new(sources.len(), TieBreak::Coordinates);
+
+    for &(x, y) in sources {
+        states.insert((x, y), NodeState { cost: 0.0, parent: None, closed: false });
+        open_list.push((0.0, 0.0, x, y));
+    }
+
+    while let Some((x, y)) = open_list.pop() {
+        let state = states.get_mut((x, y)).unwrap();
+        // Stale entry of a node that was pushed again with a lower cost
+        if state.closed {
+            continue;
+        }
+        state.closed = true;
+        let current_cost = state.cost;
+
+        let current = grid.get_node_at_point((x, y));
+        for neighbor in grid.get_passable_neighbors(x, y) {
+            let point = (neighbor.x, neighbor.y);
+            let cost = current_cost + step_cost(current, &neighbor);
+            if cost > max_cost || states.get(point).is_some_and(|state| state.closed || cost >= state.cost) {
+                continue;
+            }
+            states.insert(point, NodeState { cost, parent: Some((x, y)), closed: false });
+            open_list.push((cost, cost, neighbor.x, neighbor.y));
+        }
+    }
+
+    CostMap { states }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_grid::{assert_cost_eq, Lcg, RandomGrid};
+
+    #[test]
+    fn flow_field_costs_match_astar() {
+        let mut rng = Lcg::new(41);
+        for case in 0..40 {
+            let grid = RandomGrid::new(&mut rng);
+            let goals: Vec<(usize, usize)> = (0..1 + rng.below(3)).filter_map(|_| grid.random_passable_node(&mut rng)).collect();
+            if goals.is_empty() {
+                continue;
+            }
+
+            // Expanded backwards from the goals like `compute_flow_field`
+            let cost_map = expand(&grid.grid, &goals, f64::INFINITY, |current, _| 1.0 / current.passability);
+            // A budget larger than any cost reaches the same nodes with the sparse storage
+            let bounded = expand(&grid.grid, &goals, 1e9, |current, _| 1.0 / current.passability);
+            assert_eq!(bounded.iter_reached().collect::<Vec<_>>(), cost_map.iter_reached().collect::<Vec<_>>(), "case {}", case);
+            for y in 0..grid.grid.get_height() {
+                for x in 0..grid.grid.get_width() {
+                    if !grid.grid.get_node_at_point((x, y)).passable {
+                        continue;
+                    }
+                    let cost = cost_map.states.get((x, y)).map(|state| state.cost);
+                    let context = format!("case {} node ({}, {})", case, x, y);
+                    assert_cost_eq(cost, grid.astar_cost(&[(x, y)], &goals), &context);
+
+                    // Following the parents reaches a goal for exactly that cost
+                    if let Some(cost) = cost {
+                        let mut path = vec![(x, y)];
+                        while let Some(next) = cost_map.get_parent(path[path.len() - 1].0, path[path.len() - 1].1) {
+                            path.push(next);
+                        }
+                        assert!(goals.contains(&path[path.len() - 1]), "{}", context);
+                        assert_cost_eq(Some(grid.path_cost(&path)), Some(cost), &context);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use napi_derive::napi;
+
+/// Cost-to-goal and next step for every hex of a grid, indexed by hex id.
+#[napi]
+pub struct FlowField {
+    costs: Vec<f64>,
+    next_hex_ids: Vec<Option<u32>>,
+    directions: Vec<Option<u32>>,
+}
+
+#[napi]
+impl FlowField {
+    pub fn new(costs: Vec<f64>, next_hex_ids: Vec<Option<u32>>, directions: Vec<Option<u32>>) -> Self {
+        FlowField { costs, next_hex_ids, directions }
+    }
+
+    /// Costs of every hex, `Infinity` for hexes that can not reach a goal.
+    #[napi(getter)]
+    pub fn get_costs(&self) -> Vec<f64> {
+        self.costs.clone()
+    }
+
+    #[napi]
+    pub fn get_cost(&self, hex_id: u32) -> Option<f64> {
+        self.costs.get(hex_id as usize).copied().filter(|cost| cost.is_finite())
+    }
+
+    /// The neighbor to step on from `hex_id`, `null` on goals and unreachable hexes.
+    #[napi]
+    pub fn get_next_hex_id(&self, hex_id: u32) -> Option<u32> {
+        *self.next_hex_ids.get(hex_id as usize)?
+    }
+
+    /// Direction of the next hex: 0..5 for the cube offsets (1, 0, -1), (1, -1, 0), (0, -1, 1),
+    /// (-1, 0, 1), (-1, 1, 0) and (0, 1, -1).
+    #[napi]
+    pub fn get_direction(&self, hex_id: u32) -> Option<u32> {
+        *self.directions.get(hex_id as usize)?
+    }
+
+    /// Hex ids from `hex_id` to the goal it flows into, both included.
+    #[napi]
+    pub fn get_path(&self, hex_id: u32) -> Vec<u32> {
+        if self.get_cost(hex_id).is_none() {
+            return Vec::new();
+        }
+
+        let mut path = vec![hex_id];
+        let mut current = self.get_next_hex_id(hex_id);
+        while let Some(next) = current {
+            path.push(next);
+            current = self.get_next_hex_id(next);
+        }
+        path
+    }
+}
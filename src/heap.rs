@@ -1,16 +1,20 @@
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
 
+use crate::path::TieBreak;
+
 #[derive(Debug, Clone)]
 struct HeapEntry {
     cost: f64,
+    // Secondary key from the tie-break policy, lower first
+    tie: f64,
     x: usize,
     y: usize,
 }
 
 impl PartialEq for HeapEntry {
     fn eq(&self, other: &Self) -> bool {
-        self.x == other.x && self.y == other.y
+        self.cmp(other) == Ordering::Equal
     }
 }
 
@@ -18,8 +22,12 @@ impl Eq for HeapEntry {}
 
 impl Ord for HeapEntry {
     fn cmp(&self, other: &Self) -> Ordering {
-        // Use f64's total_cmp for more efficient comparison without unwrap
-        other.cost.total_cmp(&self.cost)
+        // Reversed for a min-heap: lower cost, then lower tie key, then lower row and column
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then(other.tie.total_cmp(&self.tie))
+            .then((other.y, other.x).cmp(&(self.y, self.x)))
     }
 }
 
@@ -29,23 +37,34 @@ impl PartialOrd for HeapEntry {
     }
 }
 
+/// Min-heap of open nodes. Entries are totally ordered, so nodes pop in the same order whatever
+/// order they were pushed in: by cost, then by `tie_break`, then by position.
 pub struct CustomHeap {
     heap: BinaryHeap<HeapEntry>,
+    tie_break: TieBreak,
 }
 
 impl CustomHeap {
-    pub fn new(capacity: usize) -> Self {
+    pub fn new(capacity: usize, tie_break: TieBreak) -> Self {
         Self {
             heap: BinaryHeap::with_capacity(capacity),
+            tie_break,
         }
     }
 
-    pub fn push(&mut self, value: (f64, usize, usize)) {
-        let entry = HeapEntry {
-            cost: value.0,
-            x: value.1,
-            y: value.2,
+    fn entry(&self, value: (f64, f64, usize, usize)) -> HeapEntry {
+        let (cost, g, x, y) = value;
+        let tie = match self.tie_break {
+            TieBreak::HigherG => -g,
+            TieBreak::LowerG => g,
+            TieBreak::Coordinates => 0.0,
         };
+        HeapEntry { cost, tie, x, y }
+    }
+
+    /// Pushes a node as `(cost, g, x, y)`, where `g` is the cost of reaching it.
+    pub fn push(&mut self, value: (f64, f64, usize, usize)) {
+        let entry = self.entry(value);
         self.heap.push(entry);
     }
 
@@ -53,20 +72,19 @@ impl CustomHeap {
         self.heap.pop().map(|entry| (entry.x, entry.y))
     }
 
-    pub fn update(&mut self, value: (f64, usize, usize)) {
-        // Create a temporary entry for comparison
-        let temp_entry = HeapEntry {
-            cost: value.0,
-            x: value.1,
-            y: value.2,
-        };
+    pub fn update(&mut self, value: (f64, f64, usize, usize)) {
+        let entry = self.entry(value);
 
         // More efficient update using retain
-        self.heap.retain(|e| e.x != temp_entry.x || e.y != temp_entry.y);
-        self.heap.push(temp_entry);
+        self.heap.retain(|e| e.x != entry.x || e.y != entry.y);
+        self.heap.push(entry);
+    }
+
+    pub fn peek_cost(&self) -> Option<f64> {
+        self.heap.peek().map(|entry| entry.cost)
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.heap.is_empty()
+    pub fn len(&self) -> usize {
+        self.heap.len()
     }
-}
\ No newline at end of file
+}
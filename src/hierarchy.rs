@@ -0,0 +1,524 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::heap::CustomHeap;
+use crate::path::{PathOptions, PathStatus, SearchOutcome, TieBreak};
+use crate::temp_search_grid::TempSearchGrid;
+
+pub const DEFAULT_CLUSTER_SIZE: u32 = 16;
+
+// Entrances at least this long get a transition at both ends instead of one in the middle
+const LONG_ENTRANCE: usize = 6;
+
+type NodePoint = (usize, usize);
+
+// Node rectangle of a cluster, the maximums are exclusive
+#[derive(Debug, Clone, Copy)]
+struct ClusterBounds {
+    min_x: usize,
+    min_y: usize,
+    max_x: usize,
+    max_y: usize,
+}
+
+impl ClusterBounds {
+    fn contains(&self, (x, y): NodePoint) -> bool {
+        x >= self.min_x && x < self.max_x && y >= self.min_y && y < self.max_y
+    }
+
+    fn len(&self) -> usize {
+        (self.max_x - self.min_x) * (self.max_y - self.min_y)
+    }
+
+    fn index(&self, (x, y): NodePoint) -> usize {
+        (y - self.min_y) * (self.max_x - self.min_x) + (x - self.min_x)
+    }
+}
+
+// Dijkstra restricted to the nodes of one cluster. A backward search follows the links in
+// reverse, so its costs are the costs of reaching the sources instead of leaving them
+struct ClusterSearch {
+    bounds: ClusterBounds,
+    costs: Vec<f64>,
+    parents: Vec<Option<NodePoint>>,
+}
+
+impl ClusterSearch {
+    fn run(grid: &TempSearchGrid, bounds: ClusterBounds, sources: &[NodePoint], backward: bool) -> Self {
+        let len = bounds.len();
+        let mut costs = vec![f64::INFINITY; len];
+        let mut parents = vec![None; len];
+        let mut closed = vec![false; len];
+        let mut open_list = CustomHeap::new(len, TieBreak::Coordinates);
+
+        // Impassable targets are never reached, not even from their own cluster
+        for &(x, y) in sources.iter().filter(|&&point| grid.get_node_at_point(point).passable) {
+            costs[bounds.index((x, y))] = 0.0;
+            open_list.push((0.0, 0.0, x, y));
+        }
+
+        while let Some((x, y)) = open_list.pop() {
+            let index = bounds.index((x, y));
+            if closed[index] {
+                continue;
+            }
+            closed[index] = true;
+
+            let leaving_cost = 1.0 / grid.get_node_at_point((x, y)).passability;
+            for neighbor in grid.get_passable_neighbors(x, y) {
+                let point = (neighbor.x, neighbor.y);
+                if !bounds.contains(point) {
+                    continue;
+                }
+
+                let neighbor_index = bounds.index(point);
+                let step_cost = if backward { leaving_cost } else { 1.0 / neighbor.passability };
+                let cost = costs[index] + step_cost;
+                if !closed[neighbor_index] && cost < costs[neighbor_index] {
+                    costs[neighbor_index] = cost;
+                    parents[neighbor_index] = Some((x, y));
+                    open_list.push((cost, cost, neighbor.x, neighbor.y));
+                }
+            }
+        }
+
+        ClusterSearch { bounds, costs, parents }
+    }
+
+    fn cost(&self, point: NodePoint) -> f64 {
+        self.costs[self.bounds.index(point)]
+    }
+
+    // Node positions from `point` to the source it was reached from
+    fn backtrace(&self, point: NodePoint) -> Vec<NodePoint> {
+        let mut path = vec![point];
+        let mut current = self.parents[self.bounds.index(point)];
+        while let Some(parent) = current {
+            path.push(parent);
+            current = self.parents[self.bounds.index(parent)];
+        }
+        path
+    }
+}
+
+// Abstract graph data of one cluster
+#[derive(Clone, Default)]
+struct Cluster {
+    // Selected transitions from the cluster into each of its neighbors
+    transitions: HashMap<usize, Vec<(NodePoint, NodePoint)>>,
+    // Transition nodes of the cluster
+    entrances: Vec<NodePoint>,
+    // In-cluster costs from a transition node to the other transition nodes of the cluster
+    intra_edges: HashMap<NodePoint, Vec<(NodePoint, f64)>>,
+    // Nodes of neighboring clusters a transition node links to
+    inter_edges: HashMap<NodePoint, Vec<NodePoint>>,
+}
+
+/// Abstract graph for hierarchical pathfinding (HPA*).
+///
+/// The grid is split into square clusters. Every run of passable links between two neighboring
+/// clusters is an entrance with one transition in its middle, or one at each end when it is long.
+/// Transition nodes are linked across the border and, inside their cluster, to each other with
+/// the cost of the cheapest path that stays in the cluster. Queries run A* over this graph and
+/// then refine every abstract edge into hexes with searches bounded by a single cluster.
+///
+/// Clones share the data of every cluster, an update copies only the clusters it changes.
+#[derive(Clone)]
+pub struct Hierarchy {
+    cluster_size: usize,
+    width: usize,
+    height: usize,
+    clusters_x: usize,
+    clusters: Vec<Arc<Cluster>>,
+}
+
+impl Hierarchy {
+    pub fn new(grid: &TempSearchGrid, cluster_size: usize) -> Self {
+        let width = grid.get_width();
+        let height = grid.get_height();
+        let clusters_x = width.div_ceil(cluster_size);
+        let clusters_len = clusters_x * height.div_ceil(cluster_size);
+
+        let mut hierarchy = Hierarchy {
+            cluster_size,
+            width,
+            height,
+            clusters_x,
+            clusters: (0..clusters_len).map(|_| Arc::new(Cluster::default())).collect(),
+        };
+
+        for cluster in 0..clusters_len {
+            for (other, links) in hierarchy.find_links(grid, cluster) {
+                // Both sides see the same links, each pair is selected once
+                if other > cluster {
+                    hierarchy.set_transitions(grid, cluster, other, links);
+                }
+            }
+        }
+        for cluster in 0..clusters_len {
+            hierarchy.rebuild_edges(grid, cluster);
+        }
+
+        hierarchy
+    }
+
+    /// Refreshes the clusters affected by a change of the passability of node (x, y): its own
+    /// cluster and every cluster it shares an entrance with, before or after the change.
+    pub fn update(&mut self, grid: &TempSearchGrid, x: usize, y: usize) {
+        let cluster = self.cluster_of((x, y));
+        let links = self.find_links(grid, cluster);
+
+        let mut affected: HashSet<usize> = self.clusters[cluster].transitions.keys().copied().collect();
+        affected.extend(links.keys().copied());
+        for &other in &affected {
+            Arc::make_mut(&mut self.clusters[cluster]).transitions.remove(&other);
+            Arc::make_mut(&mut self.clusters[other]).transitions.remove(&cluster);
+        }
+        for (other, links) in links {
+            self.set_transitions(grid, cluster, other, links);
+        }
+
+        affected.insert(cluster);
+        for other in affected {
+            self.rebuild_edges(grid, other);
+        }
+    }
+
+    /// Searches the abstract graph from the start nodes to the terminal nodes and refines the
+    /// result into a node path. `heuristic` estimates the cost to the closest terminal node.
+    ///
+    /// `expanded_nodes` counts abstract nodes. Cost and expansion limits apply to the abstract
+    /// search, a path longer than `max_length` is cut and reported as partial.
+    pub fn search<H>(
+        &self,
+        grid: &TempSearchGrid,
+        start_points: &[NodePoint],
+        terminal_points: &[NodePoint],
+        options: &PathOptions,
+        heuristic: H,
+    ) -> SearchOutcome
+    where
+        H: Fn(usize, usize) -> f64,
+    {
+        let max_expanded_nodes = options.max_expanded_nodes.unwrap_or(u32::MAX);
+        let max_cost = options.max_cost.unwrap_or(f64::INFINITY);
+        let mut limited = false;
+        let mut closest: Option<(f64, f64, NodePoint)> = None;
+
+        // Forward searches of the start clusters and backward searches of the goal clusters
+        let start_searches = self.search_clusters(grid, start_points, false);
+        let goal_searches = self.search_clusters(grid, terminal_points, true);
+
+        let mut open_list = CustomHeap::new(start_points.len(), options.tie_break.unwrap_or(TieBreak::HigherG));
+        let mut g: HashMap<NodePoint, f64> = HashMap::new();
+        let mut parents: HashMap<NodePoint, Option<NodePoint>> = HashMap::new();
+        let mut closed: HashSet<NodePoint> = HashSet::new();
+        // Cheapest known path as (cost, last abstract node)
+        let mut best: Option<(f64, NodePoint)> = None;
+        let mut expanded_nodes = 0;
+
+        // The start nodes count as explored, a search stopped before any expansion ends on one of them
+        for &(x, y) in start_points {
+            let h = heuristic(x, y);
+            if closest.is_none_or(|(closest_h, _, _)| h < closest_h) {
+                closest = Some((h, 0.0, (x, y)));
+            }
+        }
+
+        // The abstract search starts from the transition nodes and the goals of the start clusters
+        for (&cluster, start_search) in &start_searches {
+            let goals = terminal_points.iter().filter(|&&point| self.cluster_of(point) == cluster);
+            for &point in self.clusters[cluster].entrances.iter().chain(goals) {
+                let cost = start_search.cost(point);
+                if cost.is_finite() && cost <= max_cost && g.get(&point).is_none_or(|&known| cost < known) {
+                    g.insert(point, cost);
+                    parents.insert(point, None);
+                    open_list.push((cost + heuristic(point.0, point.1), cost, point.0, point.1));
+                }
+            }
+        }
+
+        while let Some((x, y)) = open_list.pop() {
+            let point = (x, y);
+            // Stale entry of a node that was pushed again with a lower cost
+            if closed.contains(&point) {
+                continue;
+            }
+
+            let point_g = g[&point];
+            let point_h = heuristic(x, y);
+            if best.is_some_and(|(best_cost, _)| point_g + point_h >= best_cost) {
+                break;
+            }
+            if expanded_nodes >= max_expanded_nodes {
+                limited = true;
+                break;
+            }
+            closed.insert(point);
+            expanded_nodes += 1;
+
+            if closest.is_none_or(|(h, closest_g, _)| point_h < h || (point_h == h && point_g < closest_g)) {
+                closest = Some((point_h, point_g, point));
+            }
+
+            if let Some(goal_search) = goal_searches.get(&self.cluster_of(point)) {
+                let total_cost = point_g + goal_search.cost(point);
+                if total_cost.is_finite() && best.is_none_or(|(best_cost, _)| total_cost < best_cost) {
+                    if total_cost > max_cost {
+                        limited = true;
+                    } else {
+                        best = Some((total_cost, point));
+                    }
+                }
+            }
+
+            let cluster = &self.clusters[self.cluster_of(point)];
+            let inter = cluster.inter_edges.get(&point).into_iter().flatten().map(|&next| {
+                (next, 1.0 / grid.get_node_at_point(next).passability)
+            });
+            let intra = cluster.intra_edges.get(&point).into_iter().flatten().copied();
+
+            for (next, cost) in inter.chain(intra) {
+                let ng = point_g + cost;
+                if ng > max_cost {
+                    limited = true;
+                    continue;
+                }
+                if closed.contains(&next) || g.get(&next).is_some_and(|&known| ng >= known) {
+                    continue;
+                }
+
+                g.insert(next, ng);
+                parents.insert(next, Some(point));
+                open_list.push((ng + heuristic(next.0, next.1), ng, next.0, next.1));
+            }
+        }
+
+        let (status, mut path) = match (best, closest) {
+            (Some((_, last)), _) => {
+                let mut path = self.refine(grid, &start_searches, &parents, last);
+                path.extend(goal_searches[&self.cluster_of(last)].backtrace(last).into_iter().skip(1));
+                (PathStatus::Found, path)
+            }
+            (None, Some((_, _, last))) if limited => (PathStatus::Partial, self.refine(grid, &start_searches, &parents, last)),
+            _ => return SearchOutcome { status: PathStatus::Unreachable, path: Vec::new(), expanded_nodes },
+        };
+
+        if let Some(max_length) = options.max_length {
+            if path.len() > max_length as usize + 1 {
+                path.truncate(max_length as usize + 1);
+                return SearchOutcome { status: PathStatus::Partial, path, expanded_nodes };
+            }
+        }
+        SearchOutcome { status, path, expanded_nodes }
+    }
+
+    fn cluster_of(&self, (x, y): NodePoint) -> usize {
+        (y / self.cluster_size) * self.clusters_x + x / self.cluster_size
+    }
+
+    fn cluster_bounds(&self, cluster: usize) -> ClusterBounds {
+        let min_x = (cluster % self.clusters_x) * self.cluster_size;
+        let min_y = (cluster / self.clusters_x) * self.cluster_size;
+        ClusterBounds {
+            min_x,
+            min_y,
+            max_x: (min_x + self.cluster_size).min(self.width),
+            max_y: (min_y + self.cluster_size).min(self.height),
+        }
+    }
+
+    // Passable links from the nodes of `cluster` into other clusters, grouped by cluster
+    fn find_links(&self, grid: &TempSearchGrid, cluster: usize) -> HashMap<usize, Vec<(NodePoint, NodePoint)>> {
+        let bounds = self.cluster_bounds(cluster);
+        let mut links: HashMap<usize, Vec<(NodePoint, NodePoint)>> = HashMap::new();
+
+        for y in bounds.min_y..bounds.max_y {
+            for x in bounds.min_x..bounds.max_x {
+                if !grid.get_node_at_point((x, y)).passable {
+                    continue;
+                }
+                for neighbor in grid.get_passable_neighbors(x, y) {
+                    let point = (neighbor.x, neighbor.y);
+                    let other = self.cluster_of(point);
+                    if other != cluster {
+                        links.entry(other).or_default().push(((x, y), point));
+                    }
+                }
+            }
+        }
+        links
+    }
+
+    // Splits the links between two clusters into entrances and stores their transitions on
+    // both sides
+    fn set_transitions(&mut self, grid: &TempSearchGrid, cluster: usize, other: usize, mut links: Vec<(NodePoint, NodePoint)>) {
+        // Selecting from the side of the lower cluster keeps the choice independent of the caller
+        if cluster > other {
+            links = links.into_iter().map(|(from, to)| (to, from)).collect();
+        }
+        links.sort_unstable();
+
+        let is_adjacent = |a: NodePoint, b: NodePoint| {
+            a == b
+                || grid
+                    .get_passable_neighbors(a.0, a.1)
+                    .iter()
+                    .any(|n| (n.x, n.y) == b)
+        };
+
+        // Links of an entrance are adjacent on both sides, so any of them can stand for the others
+        let mut entrances: Vec<Vec<(NodePoint, NodePoint)>> = Vec::new();
+        for link in links {
+            let entrance = entrances.iter_mut().find(|entrance| {
+                entrance.iter().any(|&(from, to)| is_adjacent(from, link.0) && is_adjacent(to, link.1))
+            });
+            match entrance {
+                Some(entrance) => entrance.push(link),
+                None => entrances.push(vec![link]),
+            }
+        }
+
+        let mut selected = Vec::new();
+        for entrance in entrances {
+            if entrance.len() >= LONG_ENTRANCE {
+                selected.push(entrance[0]);
+                selected.push(entrance[entrance.len() - 1]);
+            } else {
+                selected.push(entrance[entrance.len() / 2]);
+            }
+        }
+
+        let (low, high) = (cluster.min(other), cluster.max(other));
+        Arc::make_mut(&mut self.clusters[high]).transitions.insert(low, selected.iter().map(|&(from, to)| (to, from)).collect());
+        Arc::make_mut(&mut self.clusters[low]).transitions.insert(high, selected);
+    }
+
+    // Recomputes the transition nodes of a cluster and the abstract edges leaving them
+    fn rebuild_edges(&mut self, grid: &TempSearchGrid, cluster: usize) {
+        let mut inter_edges: HashMap<NodePoint, Vec<NodePoint>> = HashMap::new();
+        let mut entrances = Vec::new();
+        for &(from, to) in self.clusters[cluster].transitions.values().flatten() {
+            let targets = inter_edges.entry(from).or_default();
+            if !targets.contains(&to) {
+                targets.push(to);
+            }
+            if !entrances.contains(&from) {
+                entrances.push(from);
+            }
+        }
+        entrances.sort_unstable();
+        // The transitions come out of a hash map, the order of the edges must not depend on it
+        for targets in inter_edges.values_mut() {
+            targets.sort_unstable();
+        }
+
+        let bounds = self.cluster_bounds(cluster);
+        let mut intra_edges = HashMap::new();
+        for &from in &entrances {
+            let search = ClusterSearch::run(grid, bounds, &[from], false);
+            let edges = entrances
+                .iter()
+                .filter(|&&to| to != from && search.cost(to).is_finite())
+                .map(|&to| (to, search.cost(to)))
+                .collect();
+            intra_edges.insert(from, edges);
+        }
+
+        let data = Arc::make_mut(&mut self.clusters[cluster]);
+        data.entrances = entrances;
+        data.intra_edges = intra_edges;
+        data.inter_edges = inter_edges;
+    }
+
+    // One search per cluster, started from all the given nodes inside it
+    fn search_clusters(&self, grid: &TempSearchGrid, points: &[NodePoint], backward: bool) -> HashMap<usize, ClusterSearch> {
+        let mut grouped: HashMap<usize, Vec<NodePoint>> = HashMap::new();
+        for &point in points {
+            grouped.entry(self.cluster_of(point)).or_default().push(point);
+        }
+
+        grouped
+            .into_iter()
+            .map(|(cluster, sources)| (cluster, ClusterSearch::run(grid, self.cluster_bounds(cluster), &sources, backward)))
+            .collect()
+    }
+
+    // Node path from a start node to the abstract node `last`
+    fn refine(
+        &self,
+        grid: &TempSearchGrid,
+        start_searches: &HashMap<usize, ClusterSearch>,
+        parents: &HashMap<NodePoint, Option<NodePoint>>,
+        last: NodePoint,
+    ) -> Vec<NodePoint> {
+        let mut abstract_path = vec![last];
+        while let Some(&Some(parent)) = parents.get(abstract_path.last().unwrap()) {
+            abstract_path.push(parent);
+        }
+        abstract_path.reverse();
+
+        let first = abstract_path[0];
+        let mut path = start_searches[&self.cluster_of(first)].backtrace(first);
+        path.reverse();
+
+        for pair in abstract_path.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            let cluster = self.cluster_of(from);
+            if cluster != self.cluster_of(to) {
+                path.push(to);
+                continue;
+            }
+
+            let search = ClusterSearch::run(grid, self.cluster_bounds(cluster), &[from], false);
+            let mut segment = search.backtrace(to);
+            segment.reverse();
+            path.extend(segment.into_iter().skip(1));
+        }
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::PathStatus;
+    use crate::test_grid::{Lcg, RandomGrid};
+
+    #[test]
+    fn updates_match_a_fresh_build() {
+        let mut rng = Lcg::new(53);
+        for case in 0..40 {
+            let mut grid = RandomGrid::new(&mut rng);
+            let cluster_size = 2 + rng.below(6);
+            let mut hierarchy = Hierarchy::new(&grid.grid, cluster_size);
+
+            for change in 0..8 {
+                let point = grid.random_node(&mut rng);
+                let passability = rng.passability();
+                grid.set_passability(point, passability);
+                hierarchy.update(&grid.grid, point.0, point.1);
+
+                let fresh = Hierarchy::new(&grid.grid, cluster_size);
+                let context = format!("case {} change {}", case, change);
+                for (updated, expected) in hierarchy.clusters.iter().zip(&fresh.clusters) {
+                    assert_eq!(updated.transitions, expected.transitions, "{}", context);
+                }
+
+                for _ in 0..5 {
+                    let (Some(start), Some(terminal)) = (grid.random_passable_node(&mut rng), grid.random_passable_node(&mut rng)) else {
+                        continue;
+                    };
+                    let heuristic = |x, y| grid.heuristic(x, y, &[terminal]);
+                    let updated = hierarchy.search(&grid.grid, &[start], &[terminal], &PathOptions::default(), heuristic);
+                    let expected = fresh.search(&grid.grid, &[start], &[terminal], &PathOptions::default(), heuristic);
+                    assert_eq!(updated.status, expected.status, "{} from {:?} to {:?}", context, start, terminal);
+                    assert_eq!(updated.path, expected.path, "{} from {:?} to {:?}", context, start, terminal);
+
+                    // The abstract graph finds a path exactly when one exists
+                    assert_eq!(updated.status == PathStatus::Found, grid.astar_cost(&[start], &[terminal]).is_some(), "{}", context);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::f64::consts::PI;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde::Serialize;
+
+use crate::coords::CubeCoord;
+use crate::Point;
+
+const SQRT_3: f64 = 1.732_050_807_568_877_2;
+
+#[napi(object)]
+#[derive(Debug, Serialize, Clone, Copy)]
+pub struct PixelPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Flat-top hex layout matching the grid's odd-q columns: odd columns are drawn half a hex
+/// lower and `y` grows downwards.
+#[napi]
+pub struct HexLayout {
+    size: PixelPoint,
+    origin: PixelPoint,
+}
+
+#[napi]
+impl HexLayout {
+    /// `size` is the distance from a hex center to its corners along each axis,
+    /// `origin` is the pixel position of the center of hex (0, 0).
+    #[napi]
+    pub fn new(size: PixelPoint, origin: PixelPoint) -> Result<HexLayout, String> {
+        if size.x <= 0.0 || size.y <= 0.0 {
+            return Err(Error::new(Status::InvalidArg.to_string(), "Hex size must be positive".to_string()));
+        }
+
+        Ok(HexLayout { size, origin })
+    }
+
+    #[napi]
+    pub fn hex_to_pixel(&self, point: Point) -> PixelPoint {
+        let cube = CubeCoord::from_offset(point.x, point.y, 0);
+        let q = cube.q as f64;
+        let r = cube.r as f64;
+
+        PixelPoint {
+            x: self.origin.x + self.size.x * (1.5 * q),
+            y: self.origin.y + self.size.y * (SQRT_3 / 2.0 * q + SQRT_3 * r),
+        }
+    }
+
+    #[napi]
+    pub fn pixel_to_hex(&self, pixel: PixelPoint) -> Point {
+        let x = (pixel.x - self.origin.x) / self.size.x;
+        let y = (pixel.y - self.origin.y) / self.size.y;
+        let q = 2.0 / 3.0 * x;
+        let r = -1.0 / 3.0 * x + SQRT_3 / 3.0 * y;
+
+        let (x, y) = CubeCoord::round(q, r, -q - r).to_offset(0);
+        Point { x, y }
+    }
+
+    /// Corners of the hex, starting from the east one and going clockwise on screen.
+    #[napi]
+    pub fn hex_corners(&self, point: Point) -> Vec<PixelPoint> {
+        let center = self.hex_to_pixel(point);
+
+        (0..6)
+            .map(|corner| {
+                let angle = PI / 3.0 * corner as f64;
+                PixelPoint {
+                    x: center.x + self.size.x * angle.cos(),
+                    y: center.y + self.size.y * angle.sin(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout() -> HexLayout {
+        HexLayout::new(PixelPoint { x: 10.0, y: 8.0 }, PixelPoint { x: 100.0, y: 50.0 }).unwrap()
+    }
+
+    #[test]
+    fn odd_columns_are_drawn_lower() {
+        let layout = layout();
+        let origin = layout.hex_to_pixel(Point { x: 0, y: 0 });
+        assert_eq!((origin.x, origin.y), (100.0, 50.0));
+
+        let odd = layout.hex_to_pixel(Point { x: 1, y: 0 });
+        assert!((odd.x - 115.0).abs() < 1e-9 && (odd.y - (50.0 + 4.0 * SQRT_3)).abs() < 1e-9, "{:?}", odd);
+        let below = layout.hex_to_pixel(Point { x: 0, y: 1 });
+        assert!((below.x - 100.0).abs() < 1e-9 && (below.y - (50.0 + 8.0 * SQRT_3)).abs() < 1e-9, "{:?}", below);
+    }
+
+    #[test]
+    fn pixels_inside_a_hex_map_back_to_it() {
+        let layout = layout();
+        for x in -6..6 {
+            for y in -6..6 {
+                let point = Point { x, y };
+                let center = layout.hex_to_pixel(point);
+                // Points well inside the hex, in every direction from its center
+                for step in 0..12 {
+                    let angle = PI / 6.0 * step as f64;
+                    let pixel = PixelPoint { x: center.x + 6.0 * angle.cos(), y: center.y + 5.0 * angle.sin() };
+                    let hex = layout.pixel_to_hex(pixel);
+                    assert_eq!((hex.x, hex.y), (x, y), "{:?}", pixel);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn corners_start_east_and_lie_on_the_hex_size() {
+        let layout = layout();
+        let center = layout.hex_to_pixel(Point { x: 3, y: -2 });
+        let corners = layout.hex_corners(Point { x: 3, y: -2 });
+        assert_eq!(corners.len(), 6);
+        assert!((corners[0].x - (center.x + 10.0)).abs() < 1e-9 && (corners[0].y - center.y).abs() < 1e-9);
+        // Corner 1 is below the center, as `y` grows downwards
+        assert!(corners[1].y > center.y);
+        for corner in &corners {
+            let (dx, dy) = ((corner.x - center.x) / 10.0, (corner.y - center.y) / 8.0);
+            assert!((dx * dx + dy * dy - 1.0).abs() < 1e-9, "{:?}", corner);
+        }
+    }
+
+    #[test]
+    fn sizes_must_be_positive() {
+        for size in [PixelPoint { x: 0.0, y: 1.0 }, PixelPoint { x: 1.0, y: -1.0 }] {
+            assert!(HexLayout::new(size, PixelPoint { x: 0.0, y: 0.0 }).is_err());
+        }
+    }
+}
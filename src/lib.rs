@@ -1,9 +1,40 @@
 mod temp_search_grid;
 mod temp_node;
 mod heap;
+mod coords;
+mod visibility;
+mod layout;
+mod dijkstra;
+mod flow_field;
+mod path;
+mod bidirectional;
+mod hierarchy;
+mod replanning;
+mod astar;
+mod search_view;
+mod async_tasks;
+mod batch;
+mod profile;
+mod waypoints;
+mod alternatives;
+mod smoothing;
+#[cfg(test)]
+mod test_grid;
+#[cfg(test)]
+mod tests;
 
 use std::collections::{HashMap, HashSet};
-use heap::CustomHeap;
+use std::sync::Arc;
+pub use coords::*;
+pub use visibility::*;
+pub use layout::*;
+pub use flow_field::*;
+pub use path::*;
+pub use async_tasks::*;
+pub use profile::*;
+use hierarchy::Hierarchy;
+use replanning::Planner;
+use search_view::{GridGeometry, SearchView};
 use napi_derive::napi;
 use serde::Serialize;
 use napi::bindgen_prelude::*;
@@ -35,7 +66,7 @@ impl AsRef<str> for CustomError {
   }
 
 #[napi]
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum SecurityIndexType {
     NoBattles,
     Safe,
@@ -43,12 +74,21 @@ pub enum SecurityIndexType {
     FreePvP,
   }
 
+#[napi]
+#[derive(Debug, PartialEq)]
+pub enum WrapMode {
+    None,
+    /// Column `max_x` is adjacent to column `min_x` (cylindrical map)
+    Horizontal,
+}
+
 #[napi(object)]
 pub struct HexBase {
     pub x: i32,                  
     pub y: i32,                     
     pub passability: f64, 
     pub battleground: Option<String>,
+    /// One of `no_battles`, `safe`, `not_safe` or `free_pvp` (default: `not_safe`)
     pub security_index: Option<String>,
 }
 
@@ -65,7 +105,7 @@ impl HexBase {
 }
 
 #[napi(object)]
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Clone, Copy)]
 pub struct Point {
     pub x: i32,
     pub y: i32,
@@ -96,6 +136,20 @@ pub struct Hex {
     pub security_index: String,
 }
 
+#[napi(object)]
+pub struct PathBetweenSets {
+    pub start_id: u32,
+    pub target_id: u32,
+    pub path: Vec<Point>,
+}
+
+#[napi(object)]
+#[derive(Debug, Serialize, Clone)]
+pub struct ReachableHex {
+    pub hex: Hex,
+    pub cost: f64,
+}
+
 impl Hex {
     /// This is the constructor
     pub fn new(id: u32, x: i32, y: i32, passability: f64, passable: bool, battleground: bool, security_index: String) -> Self {
@@ -112,15 +166,24 @@ pub struct HexGrid {
     width: i32,
     height: i32,
     hex_id_map: HashMap<i32, usize>,
-    template_search_grid: TempSearchGrid,
+    // Shared with the searches running on worker threads, copied on change while they run
+    template_search_grid: Arc<TempSearchGrid>,
     odd_incriment: i32,
-    hexes: Vec<Hex>
+    wrap_x: bool,
+    hexes: Vec<Hex>,
+    // Parsed terrain of every hex, indexed by hex id
+    terrain: Arc<[HexTerrain]>,
+    // Ids of the hexes in the grid this one was extracted from, indexed by hex id
+    source_hex_ids: Option<Vec<u32>>,
+    hierarchy: Option<Arc<Hierarchy>>,
+    planners: HashMap<u32, Planner>,
+    next_planner_id: u32,
 }
 
 #[napi]
 impl HexGrid {
     #[napi]
-    pub fn new(grid_border: GridBorder, hexes: Vec<HexBase>) -> Result<HexGrid, String> {
+    pub fn new(grid_border: GridBorder, hexes: Vec<HexBase>, wrap_mode: Option<WrapMode>) -> Result<HexGrid, String> {
         // Calculate width and height based on grid borders
         let min_x = grid_border.min_x;
         let max_x = grid_border.max_x;
@@ -141,15 +204,22 @@ impl HexGrid {
             return Err(Error::new(Status::InvalidArg.to_string(), "Grid height cannot be less than 1".to_string()));
         }
 
+        // Columns keep their offset parity across the seam only if the width is even
+        let wrap_x = wrap_mode == Some(WrapMode::Horizontal);
+        if wrap_x && width % 2 != 0 {
+            return Err(Error::new(Status::InvalidArg.to_string(), "Wrapped grid width must be even".to_string()));
+        }
+
         // Determine odd increment based on min_x
         let odd_incriment = min_x & 1;
  
         // Initialize the template search grid
-        let mut template_search_grid = TempSearchGrid::new(width.try_into().unwrap(), height.try_into().unwrap(), odd_incriment.try_into().unwrap());
+        let mut template_search_grid = TempSearchGrid::new(width.try_into().unwrap(), height.try_into().unwrap(), odd_incriment.try_into().unwrap(), wrap_x);
 
         // Initialize hex storage
         let mut hexes_out = Vec::with_capacity((hexes_len) as usize);
         let mut hex_id_map = HashMap::new();
+        let mut terrain = vec![HexTerrain { battleground: false, security_index: SecurityIndexType::NotSafe }; hexes_len as usize];
 
         // Map HexBase to Hex
         for hex_idx in 0..hexes_len {
@@ -172,13 +242,15 @@ impl HexGrid {
             hexes_out.push(hex.clone());
 
             // Update passability in the search grid
-            let point = (&hex.x - min_x, &hex.y - min_y);
+            let point = (hex.x - min_x, hex.y - min_y);
+            terrain[(point.1 * width + point.0) as usize] = HexTerrain {
+                battleground: hex.battleground,
+                security_index: SecurityIndexType::from_security_index(&hex.security_index)
+                    .map_err(|e| Error::new(Status::InvalidArg.to_string(), e))?,
+            };
 
             if !hex.passable {
                 let _ = template_search_grid.set_node_passable(point.0.try_into().unwrap(), point.1.try_into().unwrap(), false);
-                let _ = template_search_grid
-                    .recheck_node_passable(point.0.try_into().unwrap(), point.1.try_into().unwrap())
-                    .map_err(|e| format!("Failed to set node passable: {}", e));
             }
 
             let _ = template_search_grid
@@ -186,7 +258,7 @@ impl HexGrid {
                 .map_err(|e| format!("Failed to set node passability: {}", e));
         }
 
-        let mut hex_grid = HexGrid {
+        let hex_grid = HexGrid {
             min_x,
             max_x,
             min_y,
@@ -194,20 +266,95 @@ impl HexGrid {
             width,
             height,
             odd_incriment,
-            template_search_grid,
+            wrap_x,
+            template_search_grid: Arc::new(template_search_grid),
             hex_id_map,
             hexes: hexes_out,
+            terrain: terrain.into(),
+            source_hex_ids: None,
+            hierarchy: None,
+            planners: HashMap::new(),
+            next_planner_id: 0,
         };
 
-        // Cache neighbors after all nodes are updated
-        let _ = hex_grid.template_search_grid
-            .compute_neighbor_nodes_cache()
-            .map_err(|e| format!("Failed to compute neighbor nodes cache: {}", e));
-
         // Return the new HexGrid
         Ok(hex_grid)
     }
 
+    /// Copies a rectangular region into a new independent grid.
+    #[napi]
+    pub fn extract(&self, border: GridBorder) -> Result<HexGrid, String> {
+        if !self.is_within_boundaries(border.min_x, border.min_y) || !self.is_within_boundaries(border.max_x, border.max_y) {
+            return Err(Error::new(Status::InvalidArg.to_string(), "Extracted region is out of boundaries".to_string()));
+        }
+        self.extract_region(border, None, |_, _| true)
+    }
+
+    /// Copies the hexes within `radius` of `center` into a new independent grid. The grid covers
+    /// the bounding rectangle of the area, hexes outside the area are made impassable.
+    ///
+    /// On a wrapped grid the columns of an area crossing the seam stay next to each other, the
+    /// hexes past the seam get an x below `minX` or above `maxX`. An area as wide as the grid
+    /// covers all of its columns and wraps like it.
+    #[napi]
+    pub fn extract_range(&self, center: Point, radius: u32) -> Result<HexGrid, String> {
+        let radius = radius as i32;
+        let wraps_around = self.wrap_x && 2 * radius + 1 >= self.width;
+        let (min_x, max_x) = if wraps_around {
+            (self.min_x, self.max_x)
+        } else if self.wrap_x {
+            (center.x - radius, center.x + radius)
+        } else {
+            ((center.x - radius).max(self.min_x), (center.x + radius).min(self.max_x))
+        };
+        let border = GridBorder {
+            min_x,
+            max_x,
+            min_y: (center.y - radius).max(self.min_y),
+            max_y: (center.y + radius).min(self.max_y),
+        };
+
+        let wrap_mode = wraps_around.then_some(WrapMode::Horizontal);
+        self.extract_region(border, wrap_mode, |x, y| {
+            self.calculate_distance_between_hexes(center, Point { x, y }) <= radius as f64
+        })
+    }
+
+    /// Id of the hex in the grid this one was extracted from.
+    #[napi]
+    pub fn get_source_hex_id(&self, hex_id: u32) -> Option<u32> {
+        self.source_hex_ids.as_ref()?.get(hex_id as usize).copied()
+    }
+
+    // Columns of `border` outside the grid are read across the seam of a wrapped grid
+    fn extract_region<F>(&self, border: GridBorder, wrap_mode: Option<WrapMode>, is_inside_region: F) -> Result<HexGrid, String>
+    where
+        F: Fn(i32, i32) -> bool,
+    {
+        let mut hexes = Vec::new();
+        let mut source_hex_ids = Vec::new();
+
+        for y in border.min_y..=border.max_y {
+            for x in border.min_x..=border.max_x {
+                let hex = self.find_hex_by_position(self.wrap_position_x(x), y).unwrap();
+                let passability = if is_inside_region(x, y) { hex.passability } else { 0.0 };
+
+                hexes.push(HexBase::new(
+                    x,
+                    y,
+                    passability,
+                    hex.battleground.then(|| String::from("battleground")),
+                    Some(hex.security_index),
+                ));
+                source_hex_ids.push(hex.id);
+            }
+        }
+
+        let mut hex_grid = HexGrid::new(border, hexes, wrap_mode)?;
+        hex_grid.source_hex_ids = Some(source_hex_ids);
+        Ok(hex_grid)
+    }
+
     pub fn transform_hex_point_to_node_point(&self, hex: &Hex) -> (i32, i32) {
         (hex.x - self.min_x, hex.y - self.min_y)
     }
@@ -228,7 +375,7 @@ impl HexGrid {
 
     #[napi(getter)]
     pub fn getwidth(&self) -> Result<i32> {
-        Ok(self.width.into())
+        Ok(self.width)
     }
 
     fn odd_increment(&self) -> i32 {
@@ -237,7 +384,7 @@ impl HexGrid {
 
     #[napi(getter)]
     pub fn get_height(&self) -> Result<i32> {
-        Ok(self.height.into())
+        Ok(self.height)
     }
 
     #[napi]
@@ -248,7 +395,52 @@ impl HexGrid {
 
     #[napi]
     pub fn calculate_distance_between_hexes(&self, point_a: Point, point_b: Point) -> f64 {
-        Self::heuristic_odd_q(point_a.x.try_into().unwrap(), point_a.y.try_into().unwrap(), point_b.x, point_b.y)
+        let cube_a = self.point_to_cube(point_a);
+        cube_a.distance(self.nearest_wrapped_cube(cube_a, self.point_to_cube(point_b))) as f64
+    }
+
+    #[napi]
+    pub fn point_to_cube(&self, point: Point) -> CubeCoord {
+        CubeCoord::from_offset(point.x, point.y, 0)
+    }
+
+    #[napi]
+    pub fn cube_to_point(&self, cube: CubeCoord) -> Result<Point, String> {
+        Ok(self.offset_point(cube.validate()?))
+    }
+
+    fn offset_point(&self, cube: CubeCoord) -> Point {
+        let (x, y) = cube.to_offset(0);
+        Point { x, y }
+    }
+
+    fn node_to_cube(&self, x: usize, y: usize) -> CubeCoord {
+        let hex_point = self.transform_node_point_to_hex_point(x, y);
+        CubeCoord::from_offset(hex_point.0, hex_point.1, 0)
+    }
+
+    fn geometry(&self) -> GridGeometry {
+        GridGeometry {
+            min_x: self.min_x,
+            min_y: self.min_y,
+            width: self.width,
+            odd_increment: self.odd_increment(),
+            wrap_x: self.wrap_x,
+        }
+    }
+
+    fn nearest_wrapped_cube(&self, from: CubeCoord, cube: CubeCoord) -> CubeCoord {
+        self.geometry().nearest_wrapped_cube(from, cube)
+    }
+
+    #[napi]
+    pub fn point_to_axial(&self, point: Point) -> AxialCoord {
+        self.point_to_cube(point).to_axial()
+    }
+
+    #[napi]
+    pub fn axial_to_point(&self, axial: AxialCoord) -> Point {
+        self.offset_point(CubeCoord::from_axial(axial))
     }
 
     #[napi]
@@ -267,6 +459,31 @@ impl HexGrid {
         None
     }
 
+    fn find_hex_by_cube(&self, cube: CubeCoord) -> Option<Hex> {
+        let (x, y) = cube.to_offset(0);
+        self.find_hex_by_position(self.wrap_position_x(x), y)
+    }
+
+    // Drops the repeated copies of hexes that a walk wider than a wrapped grid reaches again
+    // across the seam, keeping the order of the first ones
+    fn unique_hexes(&self, hexes: impl Iterator<Item = Hex>) -> Vec<Hex> {
+        if !self.wrap_x {
+            return hexes.collect();
+        }
+
+        let mut seen = HashSet::new();
+        hexes.filter(|hex| seen.insert(hex.id)).collect()
+    }
+
+    // Moves a column that left a wrapped grid back onto it
+    fn wrap_position_x(&self, x: i32) -> i32 {
+        if self.wrap_x {
+            self.min_x + (x - self.min_x).rem_euclid(self.width)
+        } else {
+            x
+        }
+    }
+
     // Helper function to check if (x, y) is within grid boundaries
     fn is_within_boundaries(&self, x: i32, y: i32) -> bool {
         x >= self.min_x && x <= self.max_x && y >= self.min_y && y <= self.max_y
@@ -277,10 +494,7 @@ impl HexGrid {
         let hex = self.hexes.get(hex_id); // get returns Option<&Hex>
         
         // If the hex exists, return a Point, otherwise return None
-        match hex {
-            Some(hex) => Some(hex.clone()),
-            None => None,  // Return None if the hex does not exist
-        }
+        hex.cloned()
     }
 
     #[napi]
@@ -291,8 +505,8 @@ impl HexGrid {
         // Retrieve the starting node from the search grid using the position
         let hex_point = self.transform_hex_point_to_node_point(&position);
         let start_node = self.template_search_grid.get_node_at_point(((hex_point.0 as usize), (hex_point.1 as usize)));
-        // Get the neighbors of the node, assuming get_neighbors_passable_nodes_from_cache exists
-        let neighbors = self.template_search_grid.get_neighbors_passable_nodes_from_cache(start_node.x, start_node.y).unwrap();
+        // Get the passable neighbors of the node
+        let neighbors = self.template_search_grid.get_passable_neighbors(start_node.x, start_node.y);
 
         // Convert the nodes into corresponding Hexes and return
         let hexes: Vec<Hex> = neighbors
@@ -315,54 +529,169 @@ impl HexGrid {
         self.template_search_grid.is_node_on_border_of_impassable_area(node.x, node.y)
     }
 
-    pub fn is_hex_in_border_by_id(&self, id: i32) -> bool {
-        // Check if the ID is within the valid range (0 to width * height)
-        id >= 0 && id < ((self.height * self.width) as u32).try_into().unwrap()
-    }
-
     #[napi]
-    pub fn get_hexes_within_range(&self, center: Point, range: i32) -> Vec<Hex> {
-        let center_hex = match self.find_hex_by_position(center.x, center.y) {
-            Some(hex) => hex,
-            None => return Vec::new(), // Return empty if center hex isn't found
-        };
+    pub fn get_hexes_within_range(&self, center: Point, range: i32, passable_only: Option<bool>) -> Vec<Hex> {
+        if !self.is_within_boundaries(center.x, center.y) {
+            return Vec::new(); // Return empty if center hex isn't found
+        }
 
-        let center_hex_id = center_hex.id;
+        let passable_only = passable_only.unwrap_or(false);
+        let center_cube = self.point_to_cube(center);
         let mut result = Vec::new();
 
-        for i in -range..=range {
-            for l in -range..=range {
-                let hex_id = center_hex_id as i32 + (i * self.width) + l;
+        // Every hex within `range` lies inside the (2r+1)x(2r+1) offset window, clip it to the grid
+        // and walk it row by row so the result comes out ordered by hex id
+        let (from_y, to_y) = ((center.y - range).max(self.min_y), (center.y + range).min(self.max_y));
+        let (from_x, to_x) = if self.wrap_x {
+            (center.x - range, center.x + range)
+        } else {
+            ((center.x - range).max(self.min_x), (center.x + range).min(self.max_x))
+        };
 
-                // Check if the hex is within the border of the grid
-                if self.is_hex_in_border_by_id(hex_id) {
-                    if let Some(hex) = self.get_hex_by_id(hex_id.try_into().unwrap()) {
-                        result.push(hex.clone()); // Add the hex to the result
+        for y in from_y..=to_y {
+            for x in from_x..=to_x {
+                if center_cube.distance(CubeCoord::from_offset(x, y, 0)) > range {
+                    continue;
+                }
+                if let Some(hex) = self.find_hex_by_position(self.wrap_position_x(x), y) {
+                    if !passable_only || hex.passable {
+                        result.push(hex);
                     }
                 }
             }
         }
 
+        // Windows crossing the seam of a wrapped grid come out of order and, when wider
+        // than the grid, reach the same hexes twice
+        if self.wrap_x {
+            result.sort_by_key(|hex| hex.id);
+            result.dedup_by_key(|hex| hex.id);
+        }
+
         result
     }
+    #[napi]
+    pub fn get_ring(&self, center: Point, radius: u32) -> Vec<Hex> {
+        if !self.is_within_boundaries(center.x, center.y) {
+            return Vec::new();
+        }
+
+        let ring = self.point_to_cube(center).ring(radius).into_iter().filter_map(|cube| self.find_hex_by_cube(cube));
+        self.unique_hexes(ring)
+    }
+
+    #[napi]
+    pub fn get_spiral(&self, center: Point, radius: u32) -> Vec<Hex> {
+        if !self.is_within_boundaries(center.x, center.y) {
+            return Vec::new();
+        }
+
+        let spiral = self.point_to_cube(center).spiral(radius).into_iter().filter_map(|cube| self.find_hex_by_cube(cube));
+        self.unique_hexes(spiral)
+    }
+
+    #[napi]
+    pub fn get_line(&self, point_a: Point, point_b: Point) -> Vec<Hex> {
+        let cube_a = self.point_to_cube(point_a);
+        cube_a
+            .line_to(self.nearest_wrapped_cube(cube_a, self.point_to_cube(point_b)))
+            .into_iter()
+            .filter_map(|cube| self.find_hex_by_cube(cube))
+            .collect()
+    }
+
+    #[napi]
+    pub fn has_line_of_sight(&self, from: Point, to: Point, options: Option<LineOfSightOptions>) -> Result<LineOfSight, String> {
+        if !self.is_within_boundaries(from.x, from.y) || !self.is_within_boundaries(to.x, to.y) {
+            return Err(Error::new(Status::InvalidArg.to_string(), "Hex is out of boundaries".to_string()));
+        }
+
+        let options = options.unwrap_or_default();
+        let line = self.get_line(from, to);
+        let target_index = line.len() - 1;
+
+        // The origin never blocks its own view
+        for (index, hex) in line.into_iter().enumerate().skip(1) {
+            if index == target_index && !options.include_target.unwrap_or(false) {
+                break;
+            }
+
+            let node_point = self.transform_hex_point_to_node_point(&hex);
+            let node = self.template_search_grid.get_node_at_point(((node_point.0 as usize), (node_point.1 as usize)));
+            if options.is_blocking(&hex, node.passable) {
+                return Ok(LineOfSight { visible: false, blocking_hex: Some(hex) });
+            }
+        }
+
+        Ok(LineOfSight { visible: true, blocking_hex: None })
+    }
+
+    #[napi]
+    pub fn compute_fov(&self, origin: Point, radius: u32) -> Result<Vec<Hex>, String> {
+        if !self.is_within_boundaries(origin.x, origin.y) {
+            return Err(Error::new(Status::InvalidArg.to_string(), "Hex is out of boundaries".to_string()));
+        }
+
+        let visible = visibility::shadowcast(self.point_to_cube(origin), radius, |cube| {
+            let (x, y) = cube.to_offset(0);
+            let x = self.wrap_position_x(x);
+            if !self.is_within_boundaries(x, y) {
+                return None;
+            }
+            let node = self.template_search_grid.get_node_at_point((((x - self.min_x) as usize), ((y - self.min_y) as usize)));
+            Some(!node.passable)
+        });
+
+        // Copies of a hex seen across the seam count once, it is visible if any of them is
+        Ok(self.unique_hexes(visible.into_iter().filter_map(|cube| self.find_hex_by_cube(cube))))
+    }
+
+    #[napi]
+    pub fn rotate_point(&self, point: Point, center: Point, steps: i32) -> Point {
+        self.offset_point(self.point_to_cube(point).rotate_around(self.point_to_cube(center), steps))
+    }
+
+    #[napi]
+    pub fn rotate_points(&self, points: Vec<Point>, center: Point, steps: i32) -> Vec<Point> {
+        points
+            .into_iter()
+            .map(|point| self.rotate_point(point, center, steps))
+            .collect()
+    }
+
+    #[napi]
+    pub fn reflect_point(&self, point: Point, center: Point, axis: HexAxis) -> Point {
+        self.offset_point(self.point_to_cube(point).reflect_around(self.point_to_cube(center), axis))
+    }
+
+    #[napi]
+    pub fn reflect_points(&self, points: Vec<Point>, center: Point, axis: HexAxis) -> Vec<Point> {
+        points
+            .into_iter()
+            .map(|point| self.reflect_point(point, center, axis))
+            .collect()
+    }
+
     #[napi]
     pub fn get_border_passable_neighbors(&self, point: Point) -> Vec<Hex> {
         
         let node_point =  (point.x - self.min_x, point.y - self.min_y);
         let node = self.template_search_grid.get_node_at_point(((node_point.0 as usize), (node_point.1 as usize)));
-        let neighbours = self.template_search_grid.get_border_passable_neighbors(node.x, node.y).unwrap_or(Vec::new());
+        let neighbours = self.template_search_grid.get_border_passable_neighbors(node.x, node.y);
         
-        let hexes = neighbours.into_iter()
+        neighbours.into_iter()
         .map(|(x, y)| {
             // Transform node point back into hex point
             let hex_point = ((x as i32) + self.min_x, (y as i32) + self.min_y);
             let hex: Hex = self.find_hex_by_position(hex_point.0, hex_point.1).unwrap();
             hex
         })
-        .collect();
-        hexes
+        .collect()
     }
 
+    /// Hexes entering and leaving the area of `getHexesWithinRange` when its center moves from
+    /// `oldCenter` to `newCenter`, as `[added, deleted]` keyed by hex id. Both areas follow the
+    /// same clipping and wrapping rules.
     #[napi(ts_return_type="Record<number, Hex>[]")]
     pub fn get_difference_between_areas_with_neigbor_centers(
         &self,
@@ -370,191 +699,147 @@ impl HexGrid {
         new_center: Point,
         range: i32,
     ) -> Vec<Value> {
-        let center_hex = self.find_hex_by_position(new_center.x, new_center.y).unwrap();
-        let center_hex_id = center_hex.id as i32;
-    
-        let mut added: HashMap<i32, Hex> = HashMap::new();
-        let mut deleted: HashMap<i32, Hex> = HashMap::new();
-        let mut result = Vec::new();
-        let mut index: i32;
-    
-        // Left movements (x-1)
-        if new_center.x == old_center.x - 1 {
-            if new_center.y == old_center.y {
-                // Pure left
-                for i in -range..=range {
-                    // Left edge (added)
-                    index = center_hex_id + i * self.width - range;
-                    if self.is_hex_in_border_by_id(index) {
-                        added.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                    // Right edge (deleted)
-                    index = center_hex_id + i * self.width + range + 1;
-                    if self.is_hex_in_border_by_id(index) {
-                        deleted.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                }
-            } else if new_center.y == old_center.y - 1 {
-                // Left-up diagonal
-                for i in -range..=range {
-                    // Top edge (added)
-                    index = center_hex_id - range * self.width + i;
-                    if self.is_hex_in_border_by_id(index) {
-                        added.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                    // Left edge (added)
-                    index = center_hex_id - i * self.width - range;
-                    if self.is_hex_in_border_by_id(index) {
-                        added.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                    // Bottom edge (deleted)
-                    index = center_hex_id + (range + 1) * self.width + i + 1;
-                    if self.is_hex_in_border_by_id(index) {
-                        deleted.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                    // Right edge (deleted)
-                    index = center_hex_id + (i + 1) * self.width + range + 1;
-                    if self.is_hex_in_border_by_id(index) {
-                        deleted.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                }
-            } else if new_center.y == old_center.y + 1 {
-                // Left-down diagonal
-                for i in -range..=range {
-                    // Bottom edge (added)
-                    index = center_hex_id + range * self.width + i;
-                    if self.is_hex_in_border_by_id(index) {
-                        added.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                    // Left edge (added)
-                    index = center_hex_id + i * self.width - range;
-                    if self.is_hex_in_border_by_id(index) {
-                        added.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                    // Top edge (deleted)
-                    index = center_hex_id - (range + 1) * self.width + i + 1;
-                    if self.is_hex_in_border_by_id(index) {
-                        deleted.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                    // Right edge (deleted)
-                    index = center_hex_id + (i - 1) * self.width + range + 1;
-                    if self.is_hex_in_border_by_id(index) {
-                        deleted.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                }
-            }
+        let old_area: HashMap<u32, Hex> = self.get_hexes_within_range(old_center, range, None).into_iter().map(|hex| (hex.id, hex)).collect();
+        let new_area: HashMap<u32, Hex> = self.get_hexes_within_range(new_center, range, None).into_iter().map(|hex| (hex.id, hex)).collect();
+
+        let difference = |area: &HashMap<u32, Hex>, other: &HashMap<u32, Hex>| -> HashMap<u32, Hex> {
+            area.iter().filter(|(id, _)| !other.contains_key(id)).map(|(&id, hex)| (id, hex.clone())).collect()
+        };
+        let added = difference(&new_area, &old_area);
+        let deleted = difference(&old_area, &new_area);
+
+        vec![serde_json::to_value(added).unwrap(), serde_json::to_value(deleted).unwrap()]
+    }
+
+    /// Changes the passability of a hex, a passability of 0 makes it impassable. Search caches
+    /// and the hierarchy of `buildHierarchy` are updated around the hex only.
+    #[napi]
+    pub fn set_hex_passability(&mut self, hex_id: u32, passability: f64) -> Result<(), String> {
+        if passability < 0.0 || !passability.is_finite() {
+            return Err(Error::new(Status::InvalidArg.to_string(), "Passability must be a non-negative number".to_string()));
         }
-        // Right movements (x+1)
-        else if new_center.x == old_center.x + 1 {
-            if new_center.y == old_center.y {
-                // Pure right
-                for i in -range..=range {
-                    // Right edge (added)
-                    index = center_hex_id + i * self.width + range;
-                    if self.is_hex_in_border_by_id(index) {
-                        added.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                    // Left edge (deleted)
-                    index = center_hex_id + i * self.width - range - 1;
-                    if self.is_hex_in_border_by_id(index) {
-                        deleted.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                }
-            } else if new_center.y == old_center.y - 1 {
-                // Right-up diagonal
-                for i in -range..=range {
-                    // Top edge (added)
-                    index = center_hex_id - range * self.width + i;
-                    if self.is_hex_in_border_by_id(index) {
-                        added.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                    // Right edge (added)
-                    index = center_hex_id - i * self.width + range;
-                    if self.is_hex_in_border_by_id(index) {
-                        added.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                    // Bottom edge (deleted)
-                    index = center_hex_id + (range + 1) * self.width + i - 1;
-                    if self.is_hex_in_border_by_id(index) {
-                        deleted.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                    // Left edge (deleted)
-                    index = center_hex_id + (i + 1) * self.width - range - 1;
-                    if self.is_hex_in_border_by_id(index) {
-                        deleted.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                }
-            } else if new_center.y == old_center.y + 1 {
-                // Right-down diagonal
-                for i in -range..=range {
-                    // Bottom edge (added)
-                    index = center_hex_id + range * self.width + i;
-                    if self.is_hex_in_border_by_id(index) {
-                        added.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                    // Right edge (added)
-                    index = center_hex_id + i * self.width + range;
-                    if self.is_hex_in_border_by_id(index) {
-                        added.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                    // Top edge (deleted)
-                    index = center_hex_id - (range + 1) * self.width + i - 1;
-                    if self.is_hex_in_border_by_id(index) {
-                        deleted.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                    // Left edge (deleted)
-                    index = center_hex_id + (i - 1) * self.width - range - 1;
-                    if self.is_hex_in_border_by_id(index) {
-                        deleted.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                }
+
+        let hex = self.require_hex_by_id(hex_id)?;
+        let (x, y) = self.transform_hex_point_to_node_point(&hex);
+        let (x, y) = (x as usize, y as usize);
+        let passable = passability > 0.0;
+
+        let hex = &mut self.hexes[hex_id as usize];
+        hex.passability = passability;
+        hex.passable = passable;
+
+        let search_grid = Arc::make_mut(&mut self.template_search_grid);
+        search_grid
+            .set_node_passable(x, y, passable)
+            .and_then(|_| search_grid.set_node_passability(x, y, passability))
+            .map_err(|e| Error::new(Status::GenericFailure.to_string(), e))?;
+
+        if let Some(hierarchy) = self.hierarchy.as_mut() {
+            Arc::make_mut(hierarchy).update(&self.template_search_grid, x, y);
+        }
+
+        let mut planners = std::mem::take(&mut self.planners);
+        for planner in planners.values_mut() {
+            planner.notify_changed(&self.template_search_grid, (x, y), &|from, to| self.node_distance(from, to));
+        }
+        self.planners = planners;
+        Ok(())
+    }
+
+    /// Splits the grid into square clusters of `clusterSize` hexes (default 16) and precomputes
+    /// the abstract graph used by `SearchAlgorithm.Hierarchical`, replacing any previous one.
+    #[napi]
+    pub fn build_hierarchy(&mut self, cluster_size: Option<u32>) -> Result<(), String> {
+        let cluster_size = cluster_size.unwrap_or(hierarchy::DEFAULT_CLUSTER_SIZE);
+        if cluster_size < 2 {
+            return Err(Error::new(Status::InvalidArg.to_string(), "Cluster size must be at least 2".to_string()));
+        }
+
+        self.hierarchy = Some(Arc::new(Hierarchy::new(&self.template_search_grid, cluster_size as usize)));
+        Ok(())
+    }
+
+    /// Hexes reachable from `start_id` within `budget`, with the minimal cost of reaching each
+    /// of them. Entering a hex costs `1 / passability`, as in path search.
+    #[napi]
+    pub fn get_reachable_hexes(&self, start_id: u32, budget: f64) -> Result<Vec<ReachableHex>, String> {
+        let start_hex = self.require_hex_by_id(start_id)?;
+
+        if !start_hex.passable {
+            return Err(Error::new(Status::InvalidArg.to_string(), "StartHex is not passable".to_string()));
+        }
+
+        let start_node_point = self.transform_hex_point_to_node_point(&start_hex);
+        let cost_map = dijkstra::expand(
+            &self.template_search_grid,
+            &[((start_node_point.0 as usize), (start_node_point.1 as usize))],
+            budget,
+            |_, neighbor| 1.0 / neighbor.passability,
+        );
+
+        Ok(cost_map
+            .iter_reached()
+            .map(|((x, y), cost)| {
+                let hex_point = self.transform_node_point_to_hex_point(x, y);
+                let hex = self.find_hex_by_position(hex_point.0, hex_point.1).unwrap();
+                ReachableHex { hex, cost }
+            })
+            .collect())
+    }
+
+    /// Integration field towards the closest of the goal hexes: the cost of reaching a goal from
+    /// every hex and the neighbor to step on next, computed once for any number of agents.
+    #[napi]
+    pub fn compute_flow_field(&self, goal_ids: Vec<u32>) -> Result<FlowField, String> {
+        if goal_ids.is_empty() {
+            return Err(Error::new(Status::InvalidArg.to_string(), "Goal set cannot be empty".to_string()));
+        }
+
+        let mut goal_node_points = Vec::with_capacity(goal_ids.len());
+        for goal_id in goal_ids {
+            let goal_hex = self.require_hex_by_id(goal_id)?;
+            if !goal_hex.passable {
+                return Err(Error::new(Status::InvalidArg.to_string(), "GoalHex is not passable".to_string()));
             }
+            let node_point = self.transform_hex_point_to_node_point(&goal_hex);
+            goal_node_points.push(((node_point.0 as usize), (node_point.1 as usize)));
         }
-        // Vertical movements (same x)
-        else if new_center.x == old_center.x {
-            if new_center.y == old_center.y - 1 {
-                // Pure up
-                for i in -range..=range {
-                    // Top edge (added)
-                    index = center_hex_id - range * self.width + i;
-                    if self.is_hex_in_border_by_id(index) {
-                        added.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                    // Bottom edge (deleted)
-                    index = center_hex_id + (range + 1) * self.width + i;
-                    if self.is_hex_in_border_by_id(index) {
-                        deleted.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                }
-            } else if new_center.y == old_center.y + 1 {
-                // Pure down
-                for i in -range..=range {
-                    // Bottom edge (added)
-                    index = center_hex_id + range * self.width + i;
-                    if self.is_hex_in_border_by_id(index) {
-                        added.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                    // Top edge (deleted)
-                    index = center_hex_id - (range + 1) * self.width + i;
-                    if self.is_hex_in_border_by_id(index) {
-                        deleted.insert(index, self.get_hex_by_id(index.try_into().unwrap()).unwrap().clone());
-                    }
-                }
+
+        // Expanding backwards from the goals, so the settled node is the one being entered
+        let cost_map = dijkstra::expand(
+            &self.template_search_grid,
+            &goal_node_points,
+            f64::INFINITY,
+            |current, _| 1.0 / current.passability,
+        );
+
+        let width = self.width as usize;
+        let mut costs = vec![f64::INFINITY; self.hexes.len()];
+        let mut next_hex_ids = vec![None; self.hexes.len()];
+        let mut directions = vec![None; self.hexes.len()];
+
+        for ((x, y), cost) in cost_map.iter_reached() {
+            let hex_id = y * width + x;
+            costs[hex_id] = cost;
+
+            if let Some((next_x, next_y)) = cost_map.get_parent(x, y) {
+                next_hex_ids[hex_id] = Some(self.get_hex_id_by_node_point(next_x, next_y));
+
+                let from = self.node_to_cube(x, y);
+                let step = self.nearest_wrapped_cube(from, self.node_to_cube(next_x, next_y)) - from;
+                directions[hex_id] = CUBE_DIRECTIONS.iter().position(|&direction| direction == step).map(|d| d as u32);
             }
         }
-    
-        let added_val = serde_json::to_value(added).unwrap();
-        result.push(added_val);
-    
-        let deleted_val = serde_json::to_value(deleted).unwrap();
-        result.push(deleted_val);
-        result
+
+        Ok(FlowField::new(costs, next_hex_ids, directions))
     }
+
     #[napi]
     pub fn build_path_to_impassable_border_hex(
         &mut self,
         start_id: u32,
         target_id: u32,
+        profile: Option<MovementProfile>,
     ) -> Result<Vec<Point>, String> {
         // Retrieve the start and target Hexes by ID
         let start_hex = self.get_hex_by_id(start_id).unwrap();
@@ -565,9 +850,6 @@ impl HexGrid {
             return Err(Error::new(Status::InvalidArg.to_string(), "StartHex is not passable".to_string()));
         }
 
-        // Reset the search grid for a new pathfinding operation
-        self.template_search_grid.reset();
-
         // Transform hex points to node points
         let start_node_point = self.transform_hex_point_to_node_point(&start_hex);
         let target_node_point = self.transform_hex_point_to_node_point(&target_hex);
@@ -579,19 +861,17 @@ impl HexGrid {
 
         // Calculate the path from the start to the target hex
         let path_matrix_positions = self.calculate_path_by_algorithm(
-            start_node_point,
-            target_node_point,
+            &[start_node_point],
+            &[target_node_point],
             &terminal_nodes,
+            profile,
         )?;
 
         let point_path = path_matrix_positions
         .into_iter()
         .map(|(x, y)| {
             // Transform node point back into hex point
-            let hex_point = self.transform_node_point_to_hex_point(
-                x.try_into().unwrap(),
-                y.try_into().unwrap(),
-            );
+            let hex_point = self.transform_node_point_to_hex_point(x, y);
             Point { x: hex_point.0, y: hex_point.1 }
         })
         .collect();
@@ -603,19 +883,18 @@ impl HexGrid {
         &mut self,
         start_id: u32,
         target_id: u32,
+        profile: Option<MovementProfile>,
     ) -> Result<Vec<Point>, String> {
         let start_hex = self.get_hex_by_id(start_id).unwrap();
         let target_hex = self.get_hex_by_id(target_id).unwrap();
         if !start_hex.passable {
             return Err(Error::new(Status::InvalidArg.to_string(), "StartHex is not passable".to_string()));
         }
-        self.template_search_grid.reset();
-
         let start_node_point = self.transform_hex_point_to_node_point(&start_hex);
         let target_node_point = self.transform_hex_point_to_node_point(&target_hex);
 
         let terminal_node_points = self.template_search_grid
-        .get_border_passable_neighbors(target_node_point.0.try_into().unwrap(), target_node_point.1.try_into().unwrap()).unwrap();
+        .get_border_passable_neighbors(target_node_point.0.try_into().unwrap(), target_node_point.1.try_into().unwrap());
         
         let terminal_nodes: Vec<TempNode> = terminal_node_points
         .into_iter()
@@ -625,9 +904,10 @@ impl HexGrid {
         .collect();
 
         let path_matrix_positions = self.calculate_path_by_algorithm(
-            start_node_point,
-            target_node_point,
+            &[start_node_point],
+            &[target_node_point],
             &terminal_nodes,
+            profile,
         )?;
         
         // println!("{:?}", path_matrix_positions);
@@ -635,10 +915,7 @@ impl HexGrid {
         .into_iter()
         .map(|(x, y)| {
             // Transform node point back into hex point
-            let hex_point = self.transform_node_point_to_hex_point(
-                x.try_into().unwrap(),
-                y.try_into().unwrap(),
-            );
+            let hex_point = self.transform_node_point_to_hex_point(x, y);
             Point { x: hex_point.0, y: hex_point.1 }
         })
         .collect();
@@ -649,6 +926,7 @@ impl HexGrid {
         &mut self,
         start_id: u32,
         target_id: u32,
+        profile: Option<MovementProfile>,
     ) -> Result<Vec<Point>, String> {
         // Retrieve the start and target Hexes by ID
         let start_hex = self.get_hex_by_id(start_id).unwrap();
@@ -659,9 +937,6 @@ impl HexGrid {
             return Err(Error::new(Status::InvalidArg.to_string(), "StartHex is not passable".to_string()));
         }
     
-        // Reset the search grid for a new pathfinding operation
-        self.template_search_grid.reset();
-        
         let start_node_point = self.transform_hex_point_to_node_point(&start_hex);
         let target_node_point = self.transform_hex_point_to_node_point(&target_hex);
 
@@ -672,18 +947,16 @@ impl HexGrid {
     
         // Calculate the path from the start to the target hex
         let path_matrix_positions = self.calculate_path_by_algorithm(
-            start_node_point,
-            target_node_point,
+            &[start_node_point],
+            &[target_node_point],
             &terminal_nodes,
+            profile,
         )?;
 
         let point_path: Vec<Point> = path_matrix_positions
         .into_iter()
         .map(|(x, y)| {
-            let hex_point = self.transform_node_point_to_hex_point(
-                x.try_into().unwrap(),
-                y.try_into().unwrap(),
-            );
+            let hex_point = self.transform_node_point_to_hex_point(x, y);
             Point { x: hex_point.0, y: hex_point.1 }
         })
         .collect();
@@ -693,11 +966,371 @@ impl HexGrid {
     }
     
 
+    /// Cheapest path from any of the start hexes to any of the target hexes, together with the
+    /// pair of hexes it connects.
+    #[napi]
+    pub fn build_path_between_sets(
+        &mut self,
+        start_ids: Vec<u32>,
+        target_ids: Vec<u32>,
+        profile: Option<MovementProfile>,
+    ) -> Result<PathBetweenSets, String> {
+        let endpoints = self.resolve_path_request(&PathRequest {
+            start_ids,
+            target_ids,
+            target: None,
+        })?;
+
+        let path_matrix_positions = self.calculate_path_by_algorithm(&endpoints.start_points, &endpoints.target_points, &endpoints.terminal_nodes, profile)?;
+
+        let (start_x, start_y) = path_matrix_positions[0];
+        let (target_x, target_y) = path_matrix_positions[path_matrix_positions.len() - 1];
+
+        Ok(PathBetweenSets {
+            start_id: self.get_hex_id_by_node_point(start_x, start_y),
+            target_id: self.get_hex_id_by_node_point(target_x, target_y),
+            path: path_matrix_positions
+                .into_iter()
+                .map(|(x, y)| {
+                    let hex_point = self.transform_node_point_to_hex_point(x, y);
+                    Point { x: hex_point.0, y: hex_point.1 }
+                })
+                .collect(),
+        })
+    }
+
+    /// Path search reporting the cost of every step and how much work the search took.
+    /// An unreachable target is reported by the result status rather than an error.
+    #[napi]
+    pub fn build_path(&mut self, request: PathRequest, options: Option<PathOptions>) -> Result<PathResult, String> {
+        let endpoints = self.resolve_path_request(&request)?;
+        let options = options.unwrap_or_default();
+
+        let view = self.search_view(&options)?;
+        let outcome = view.search(&endpoints, &options);
+        Ok(view.build_result(outcome))
+    }
+
+    /// Path from the start hex through every waypoint in order to the target hex, each leg being
+    /// the cheapest path between its stops. `legs` marks where every leg starts and ends in the
+    /// path and what it costs, limits of `options` apply to every leg.
+    #[napi]
+    pub fn build_path_via(
+        &mut self,
+        start_id: u32,
+        waypoint_ids: Vec<u32>,
+        target_id: u32,
+        options: Option<PathOptions>,
+    ) -> Result<WaypointPathResult, String> {
+        for &waypoint_id in &waypoint_ids {
+            if !self.require_hex_by_id(waypoint_id)?.passable {
+                return Err(Error::new(Status::InvalidArg.to_string(), format!("Waypoint {} is not passable", waypoint_id)));
+            }
+        }
+
+        let stops: Vec<u32> = std::iter::once(start_id).chain(waypoint_ids).chain(std::iter::once(target_id)).collect();
+        let legs = stops
+            .windows(2)
+            .map(|stops| {
+                self.resolve_path_request(&PathRequest {
+                    start_ids: vec![stops[0]],
+                    target_ids: vec![stops[1]],
+                    target: None,
+                })
+            })
+            .collect::<Result<Vec<SearchEndpoints>, String>>()?;
+        let options = options.unwrap_or_default();
+
+        let view = self.search_view(&options)?;
+        let (outcome, leg_ends) = waypoints::search_legs(&view, &legs, &options);
+        let path = view.build_result(outcome);
+
+        let mut leg_start = 0;
+        let legs = leg_ends
+            .into_iter()
+            .map(|leg_end| {
+                let leg = PathLeg {
+                    start_index: leg_start as u32,
+                    end_index: leg_end as u32,
+                    cost: path.costs[leg_end] - path.costs[leg_start],
+                };
+                leg_start = leg_end;
+                leg
+            })
+            .collect();
+
+        Ok(WaypointPathResult { path, legs })
+    }
+
+    /// Up to `k` distinct paths between the hexes, the cheapest first. Every path found makes its
+    /// hexes `1 + diversity` times more expensive for the following searches, so a higher
+    /// diversity trades cost for routes that share fewer hexes. Costs in the results are the
+    /// real ones, an unreachable target gives no paths. `k` must be between 1 and 16.
+    #[napi]
+    pub fn build_k_paths(
+        &mut self,
+        start_id: u32,
+        target_id: u32,
+        k: u32,
+        diversity: f64,
+        options: Option<PathOptions>,
+    ) -> Result<Vec<PathResult>, String> {
+        if k == 0 || k as usize > alternatives::MAX_PATHS {
+            return Err(Error::new(
+                Status::InvalidArg.to_string(),
+                format!("Path count must be between 1 and {}", alternatives::MAX_PATHS),
+            ));
+        }
+        if diversity <= 0.0 || !diversity.is_finite() {
+            return Err(Error::new(Status::InvalidArg.to_string(), "Diversity must be a positive number".to_string()));
+        }
+
+        let endpoints = self.resolve_path_request(&PathRequest {
+            start_ids: vec![start_id],
+            target_ids: vec![target_id],
+            target: None,
+        })?;
+        let options = options.unwrap_or_default();
+
+        let view = self.search_view(&options)?;
+        Ok(alternatives::search_alternatives(&view, &endpoints, &options, k as usize, diversity)
+            .into_iter()
+            .map(|outcome| view.build_result(outcome))
+            .collect())
+    }
+
+    /// Solves many requests at once on all cores, returning the results in request order.
+    /// Every request is validated before any search starts.
+    #[napi]
+    pub fn build_paths_batch(&mut self, requests: Vec<PathRequest>, options: Option<PathOptions>) -> Result<Vec<PathResult>, String> {
+        let endpoints = requests
+            .iter()
+            .enumerate()
+            .map(|(index, request)| {
+                self.resolve_path_request(request)
+                    .map_err(|e| Error::new(e.status, format!("Request {}: {}", index, e.reason)))
+            })
+            .collect::<Result<Vec<SearchEndpoints>, String>>()?;
+        let options = options.unwrap_or_default();
+
+        let view = self.search_view(&options)?;
+        Ok(batch::search_all(&view, &endpoints, &options)
+            .into_iter()
+            .map(|outcome| view.build_result(outcome))
+            .collect())
+    }
+
+    /// `buildPath` on the libuv thread pool. The search runs against the grid as it is when
+    /// called, later changes don't affect it.
+    #[napi]
+    pub fn build_path_async(&mut self, request: PathRequest, options: Option<PathOptions>) -> Result<AsyncTask<PathTask>, String> {
+        let endpoints = self.resolve_path_request(&request)?;
+        let options = options.unwrap_or_default();
+
+        Ok(AsyncTask::new(PathTask::new(self.search_view(&options)?, endpoints, options)))
+    }
+
+    /// `buildPathToPassableHex` on the libuv thread pool.
+    #[napi]
+    pub fn build_path_to_passable_hex_async(
+        &mut self,
+        start_id: u32,
+        target_id: u32,
+        profile: Option<MovementProfile>,
+    ) -> Result<AsyncTask<PointPathTask>, String> {
+        self.point_path_task(start_id, target_id, PathTarget::Hex, profile)
+    }
+
+    /// `buildPathToImpassableBorderHex` on the libuv thread pool.
+    #[napi]
+    pub fn build_path_to_impassable_border_hex_async(
+        &mut self,
+        start_id: u32,
+        target_id: u32,
+        profile: Option<MovementProfile>,
+    ) -> Result<AsyncTask<PointPathTask>, String> {
+        self.point_path_task(start_id, target_id, PathTarget::Hex, profile)
+    }
+
+    /// `buildPathTowardsImpassableHex` on the libuv thread pool.
+    #[napi]
+    pub fn build_path_towards_impassable_hex_async(
+        &mut self,
+        start_id: u32,
+        target_id: u32,
+        profile: Option<MovementProfile>,
+    ) -> Result<AsyncTask<PointPathTask>, String> {
+        self.point_path_task(start_id, target_id, PathTarget::ImpassableAreaBorder, profile)
+    }
+
+    fn point_path_task(
+        &mut self,
+        start_id: u32,
+        target_id: u32,
+        target: PathTarget,
+        profile: Option<MovementProfile>,
+    ) -> Result<AsyncTask<PointPathTask>, String> {
+        let endpoints = self.resolve_path_request(&PathRequest {
+            start_ids: vec![start_id],
+            target_ids: vec![target_id],
+            target: Some(target),
+        })?;
+
+        let options = PathOptions { profile, ..PathOptions::default() };
+
+        Ok(AsyncTask::new(PointPathTask::new(self.search_view(&options)?, endpoints)))
+    }
+
+    // Snapshot for a search with the given options, building the hierarchy a hierarchical
+    // search needs on first use
+    fn search_view(&mut self, options: &PathOptions) -> Result<SearchView, String> {
+        let heuristic_scale = match &options.profile {
+            Some(profile) => {
+                profile.validate().map_err(|e| Error::new(Status::InvalidArg.to_string(), e))?;
+                profile.min_multiplier()
+            }
+            None => 1.0,
+        };
+
+        if options.algorithm == Some(SearchAlgorithm::Hierarchical) && options.profile.is_none() && self.hierarchy.is_none() {
+            self.hierarchy = Some(Arc::new(Hierarchy::new(&self.template_search_grid, hierarchy::DEFAULT_CLUSTER_SIZE as usize)));
+        }
+
+        Ok(SearchView {
+            geometry: self.geometry(),
+            grid: Arc::clone(&self.template_search_grid),
+            hierarchy: self.hierarchy.clone(),
+            terrain: Arc::clone(&self.terrain),
+            profile: options.profile.clone().map(Arc::new),
+            penalties: None,
+            heuristic_scale,
+        })
+    }
+
+    /// Creates a persistent planner for a request with a single start hex and returns its id.
+    /// The planner keeps its search state between queries and repairs it after
+    /// `setHexPassability`, so re-pathing after a local change explores only around the change.
+    /// At most 8192 planners can exist at once, `removePlanner` frees them.
+    #[napi]
+    pub fn create_planner(&mut self, request: PathRequest) -> Result<u32, String> {
+        if request.start_ids.len() != 1 {
+            return Err(Error::new(Status::InvalidArg.to_string(), "Planner needs exactly one start hex".to_string()));
+        }
+        if self.planners.len() >= replanning::MAX_PLANNERS {
+            return Err(Error::new(
+                Status::InvalidArg.to_string(),
+                format!("Too many planners, at most {} can exist at once", replanning::MAX_PLANNERS),
+            ));
+        }
+
+        let endpoints = self.resolve_path_request(&request)?;
+        let start = (endpoints.start_points[0].0 as usize, endpoints.start_points[0].1 as usize);
+        let goals: Vec<(usize, usize)> = endpoints.terminal_nodes.iter().map(|n| (n.x, n.y)).collect();
+        let planner = Planner::new(&self.template_search_grid, start, &goals, &|from, to| self.node_distance(from, to));
+
+        let planner_id = self.next_planner_id;
+        self.next_planner_id += 1;
+        self.planners.insert(planner_id, planner);
+        Ok(planner_id)
+    }
+
+    /// Current path of a planner, `expandedNodes` counts the hexes its repair expanded.
+    #[napi]
+    pub fn get_planner_path(&mut self, planner_id: u32) -> Result<PathResult, String> {
+        let mut planner = self.take_planner(planner_id)?;
+        let (path, expanded_nodes) = planner.compute_path(&self.template_search_grid, &|from, to| self.node_distance(from, to));
+        self.planners.insert(planner_id, planner);
+
+        let outcome = match path {
+            Some(path) => SearchOutcome { status: PathStatus::Found, path, expanded_nodes },
+            None => SearchOutcome { status: PathStatus::Unreachable, path: Vec::new(), expanded_nodes },
+        };
+        Ok(self.search_view(&PathOptions::default())?.build_result(outcome))
+    }
+
+    /// Moves the start of a planner, e.g. after the unit made a step along its path.
+    #[napi]
+    pub fn move_planner_start(&mut self, planner_id: u32, start_id: u32) -> Result<(), String> {
+        let start_hex = self.require_hex_by_id(start_id)?;
+        if !start_hex.passable {
+            return Err(Error::new(Status::InvalidArg.to_string(), "StartHex is not passable".to_string()));
+        }
+        let (x, y) = self.transform_hex_point_to_node_point(&start_hex);
+
+        let mut planner = self.take_planner(planner_id)?;
+        planner.move_start((x as usize, y as usize), &|from, to| self.node_distance(from, to));
+        self.planners.insert(planner_id, planner);
+        Ok(())
+    }
+
+    /// Drops a planner, returns whether it existed.
+    #[napi]
+    pub fn remove_planner(&mut self, planner_id: u32) -> bool {
+        self.planners.remove(&planner_id).is_some()
+    }
+
+    fn take_planner(&mut self, planner_id: u32) -> Result<Planner, String> {
+        self.planners
+            .remove(&planner_id)
+            .ok_or_else(|| Error::new(Status::InvalidArg.to_string(), format!("Planner {} not found", planner_id)))
+    }
+
+    // Validates the start hexes and turns the targets of a request into terminal nodes
+    fn resolve_path_request(&self, request: &PathRequest) -> Result<SearchEndpoints, String> {
+        if request.start_ids.is_empty() || request.target_ids.is_empty() {
+            return Err(Error::new(Status::InvalidArg.to_string(), "Start and target sets cannot be empty".to_string()));
+        }
+
+        let mut start_node_points = Vec::with_capacity(request.start_ids.len());
+        for &start_id in &request.start_ids {
+            let start_hex = self.require_hex_by_id(start_id)?;
+            if !start_hex.passable {
+                return Err(Error::new(Status::InvalidArg.to_string(), "StartHex is not passable".to_string()));
+            }
+            start_node_points.push(self.transform_hex_point_to_node_point(&start_hex));
+        }
+
+        let mut target_points = Vec::with_capacity(request.target_ids.len());
+        let mut terminal_node_points = Vec::new();
+        for &target_id in &request.target_ids {
+            let target_hex = self.require_hex_by_id(target_id)?;
+            let target_node_point = self.transform_hex_point_to_node_point(&target_hex);
+            target_points.push(target_node_point);
+            let target_node_point = ((target_node_point.0 as usize), (target_node_point.1 as usize));
+
+            match request.target.unwrap_or(PathTarget::Hex) {
+                PathTarget::Hex => terminal_node_points.push(target_node_point),
+                PathTarget::ImpassableAreaBorder => terminal_node_points.extend(
+                    self.template_search_grid
+                        .get_border_passable_neighbors(target_node_point.0, target_node_point.1),
+                ),
+            }
+        }
+
+        terminal_node_points.sort_unstable();
+        terminal_node_points.dedup();
+        let terminal_nodes = terminal_node_points
+            .into_iter()
+            .map(|point| *self.template_search_grid.get_node_at_point(point))
+            .collect();
+
+        Ok(SearchEndpoints { start_points: start_node_points, target_points, terminal_nodes })
+    }
+
     pub fn get_hex_by_id(&self, id: u32) -> Option<Hex> {
         let hex_id = self.hex_id_map.get(&(id as i32)).unwrap();
         Some(self.hexes[*hex_id].clone())
     }
 
+    fn require_hex_by_id(&self, id: u32) -> Result<Hex, String> {
+        self.get_position_by_id(id as usize)
+            .ok_or_else(|| Error::new(Status::InvalidArg.to_string(), format!("Hex {} not found", id)))
+    }
+
+    fn get_hex_id_by_node_point(&self, x: usize, y: usize) -> u32 {
+        (y * (self.width as usize) + x) as u32
+    }
+
     pub fn get_hex_by_node_position(&self, node_point: TempNode) -> Result<Hex, String> {
         // Calculate the index in the _hexes vector
         let index = node_point.y * (self.width as usize) + node_point.x;
@@ -710,147 +1343,40 @@ impl HexGrid {
             if self.is_within_boundaries(hex.x, hex.y) {
                 Ok(hex.clone())  // Return a copy of the Hex (or clone if needed)
             } else {
-                return Err(Error::new(Status::InvalidArg.to_string(), "Hex is out of boundaries".to_string()));
+                Err(Error::new(Status::InvalidArg.to_string(), "Hex is out of boundaries".to_string()))
             }
         } else {
-            return Err(Error::new(Status::InvalidArg.to_string(), "Invalid node position: out of bounds".to_string()));
+            Err(Error::new(Status::InvalidArg.to_string(), "Invalid node position: out of bounds".to_string()))
         }
     }
 
 
     pub fn calculate_path_by_algorithm(
         &mut self,
-        start_point: (i32, i32),
-        end_point: (i32, i32),
+        start_points: &[(i32, i32)],
+        target_points: &[(i32, i32)],
         terminal_nodes: &[TempNode],
+        profile: Option<MovementProfile>,
     ) -> Result<Vec<(usize, usize)>, String> {
-        // let mut ng: f64;
-
-        let mut open_list = CustomHeap::new(self.hexes.len());
-        let terminal_node_set: HashSet<(usize, usize)> = terminal_nodes.iter().map(|n| (n.x, n.y)).collect();
-        // Select the heuristic function
-
-        let heuristic: fn(usize, usize, i32, i32) -> f64 = if self.odd_increment() != 0 {
-            Self::heuristic_even_q
-        } else {
-            Self::heuristic_odd_q
-        }; 
-
-        let start_x: usize = start_point.0.try_into().unwrap();
-        let start_y: usize = start_point.1.try_into().unwrap();
-        let end_x = end_point.0;
-        let end_y = end_point.1;
-
-        let _ = self.template_search_grid.update_node(start_x, start_y, |n| {
-            n.f = 0.0;
-            n.g = 0.0;
-            n.opened = Some(true);
-        });
-        open_list.push((0.0, start_x, start_y));
-
-        while !open_list.is_empty() {
-            let (x, y) = open_list.pop().unwrap();
-            let current_g = *self.template_search_grid.get_node_g_at_point((x, y));
-        
-            let _ = self.template_search_grid.set_node_closed(x, y, true);
-
-            if terminal_node_set.contains(&(x, y)) {
-                return Ok(self.backtrace(self.template_search_grid.get_node_at_point((x, y))));
-            }
-        
-            // Get neighbors of the current node
-            let neighbors = self
-            .template_search_grid
-            .get_neighbors_passable_nodes_from_cache(x, y)
-            .unwrap();
-            // println!("----------{}, {}-----------", x,y);
-            for neighbor in neighbors {
-
-                if neighbor.closed.unwrap_or(false) {
-                    continue; 
-                }
-        
-                // Calculate `g` score (cost to get to this neighbor)
-                let ng = current_g + (1.0 / neighbor.passability);
-                let neighbour_f;
-                let mut neighbour_h= neighbor.h;
-
-                if !neighbor.opened.unwrap_or(false) {
-
-                    if neighbor.h.is_none() {
-                        neighbour_h = Some(heuristic(neighbor.x, neighbor.y, end_x, end_y));
-
-                        let _ = self.template_search_grid.set_node_h(neighbor.x, neighbor.y, neighbour_h.unwrap());
-                    }
-                    neighbour_f = ng + neighbour_h.unwrap();
-
-                    let _ = self.template_search_grid.update_node(neighbor.x, neighbor.y, |n| {
-                        n.f = neighbour_f;
-                        n.g = ng;
-                        n.parent = Some((x, y));
-                        n.opened = Some(true);
-                    });
-                    open_list.push((neighbour_f, neighbor.x, neighbor.y));
-                    
-                } else if ng < neighbor.g {
-
-                    neighbour_f = ng + neighbour_h.unwrap();
-                let _ = self.template_search_grid.update_node(neighbor.x, neighbor.y, |n| {
-                    n.f = neighbour_f;
-                    n.g = ng;
-                    n.parent = Some((x, y));
-                });
-                    open_list.update((neighbour_f, neighbor.x, neighbor.y));
-                }
-            }
-        }
-        Err(Error::new(Status::InvalidArg.to_string(), format!(
-            "Path not found from [{}, {}] to [{}, {}]",
-            start_point.0, start_point.1, end_point.0, end_point.1
-        )))
-    }
+        let endpoints = SearchEndpoints {
+            start_points: start_points.to_vec(),
+            target_points: target_points.to_vec(),
+            terminal_nodes: terminal_nodes.to_vec(),
+        };
+        let options = PathOptions { profile, ..PathOptions::default() };
 
-    
-    fn backtrace(&self, node: &TempNode) -> Vec<(usize, usize)> {
-        let mut path = vec![(node.x, node.y)];
-        // println!("{:?}", node);
-        let mut current = node.parent;
-        while let Some(n) = current {
-            let node = self.template_search_grid.get_node_at_point(n);
-            // println!("{:?}", node);
-            path.push((n.0, n.1));
-            current = node.parent;
+        let outcome = self.search_view(&options)?.search(&endpoints, &options);
+        if outcome.status == PathStatus::Found {
+            return Ok(outcome.path);
         }
 
-        path.reverse();
-
-        path
+        Err(Error::new(Status::InvalidArg.to_string(), path_not_found_message(start_points, target_points)))
     }
 
-    fn heuristic_odd_q(x: usize, y: usize, end_x: i32, end_y: i32) -> f64 {
-        // Преобразуем все переменные в f64 для согласованности
-        let x: f64 = x as f64;
-        let mut y = y as f64;
-        let end_x = end_x as f64;
-        let mut end_y = end_y as f64;
-    
-        // Сдвиг координат y и end_y
-        y -= (x - (x % 2.0)) / 2.0;
-        end_y -= (end_x - (end_x % 2.0)) / 2.0;
-
-        // Вычисление эвристики
-        let h = (x - end_x).abs()
-        + (x + y - end_x - end_y).abs()
-        + (y - end_y).abs();
-         h / 2.0
-    }
-    
-    fn heuristic_even_q(x: usize, y: usize, end_x: i32, end_y: i32) -> f64 {
-        let yy = y - ((x + (x & 1)) / 2);
-        let end_yy = end_y - ((end_x + (end_x & 1)) / 2);
-    
-        // Heuristic formula
-        (f64::abs(x as f64 - end_x as f64) + f64::abs(x as f64 + yy as f64 - end_x as f64 - end_yy as f64) + f64::abs(yy as f64 - end_yy as f64)) / 2.0
+    // Hex distance between two node positions, across the seam on a wrapped grid
+    fn node_distance(&self, from: (usize, usize), to: (usize, usize)) -> f64 {
+        let geometry = self.geometry();
+        geometry.heuristic(from.0, from.1, &[geometry.node_cube(to.0, to.1)])
     }
 }
 
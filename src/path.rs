@@ -0,0 +1,146 @@
+use napi_derive::napi;
+
+use crate::temp_node::TempNode;
+use crate::{MovementProfile, Point};
+
+#[napi]
+#[derive(Debug, PartialEq)]
+pub enum PathStatus {
+    /// The path ends on a target hex
+    Found,
+    /// The search was cut short, the path ends on the explored hex closest to a target
+    Partial,
+    /// No target can be reached, the path is empty
+    Unreachable,
+}
+
+/// How the target hexes of a `PathRequest` are reached.
+#[napi]
+#[derive(Debug, PartialEq)]
+pub enum PathTarget {
+    /// The path ends on one of the target hexes
+    Hex,
+    /// The path ends on a passable hex bordering the impassable area a target hex belongs to
+    ImpassableAreaBorder,
+}
+
+#[napi]
+#[derive(Debug, PartialEq)]
+pub enum SearchAlgorithm {
+    /// A* growing from the start hexes
+    AStar,
+    /// A* growing from the start and the target hexes at once, meeting in the middle.
+    /// Explores fewer hexes on long routes.
+    Bidirectional,
+    /// Hierarchical A* over the cluster abstraction of `HexGrid.buildHierarchy`, built with
+    /// the default cluster size on first use. Fast on large maps, paths are near-optimal.
+    Hierarchical,
+}
+
+#[napi(object)]
+pub struct PathRequest {
+    pub start_ids: Vec<u32>,
+    pub target_ids: Vec<u32>,
+    /// Defaults to `PathTarget.Hex`
+    pub target: Option<PathTarget>,
+}
+
+/// Which of the open hexes with the same estimated path cost a search expands first. Remaining
+/// ties go to the lower row, then the lower column, so the same grid and request always give
+/// the same path.
+#[napi]
+#[derive(Debug, PartialEq)]
+pub enum TieBreak {
+    /// The hex that costs more to reach, which is the one with the lower heuristic
+    HigherG,
+    /// The hex that costs less to reach
+    LowerG,
+    /// Only the position of the hex
+    Coordinates,
+}
+
+/// Limits of a search. When one of them stops the search before a target is reached, the
+/// result is a partial path to the explored hex closest to a target.
+#[napi(object)]
+#[derive(Default)]
+pub struct PathOptions {
+    /// Maximal number of hexes to expand
+    pub max_expanded_nodes: Option<u32>,
+    /// Maximal cost of the path
+    pub max_cost: Option<f64>,
+    /// Maximal number of steps of the path
+    pub max_length: Option<u32>,
+    /// Defaults to `SearchAlgorithm.AStar`
+    pub algorithm: Option<SearchAlgorithm>,
+    /// Per-unit cost rules, a hierarchical search falls back to A* when one is given
+    pub profile: Option<MovementProfile>,
+    /// Straightens the path where a straight hex line costs no more and takes no more steps,
+    /// removing the zig-zags between equally cheap routes (default: false)
+    pub smooth: Option<bool>,
+    /// Defaults to `TieBreak.HigherG`
+    pub tie_break: Option<TieBreak>,
+}
+
+/// Straight run of the path, every step of it goes in the same direction
+#[napi(object)]
+pub struct PathSegment {
+    pub start_index: u32,
+    pub end_index: u32,
+    /// 0..5 as in `FlowField.getDirection`
+    pub direction: u32,
+}
+
+#[napi(object)]
+pub struct PathResult {
+    pub status: PathStatus,
+    pub points: Vec<Point>,
+    pub hex_ids: Vec<u32>,
+    /// Cost accumulated on reaching every hex of the path, 0 for the start hex
+    pub costs: Vec<f64>,
+    pub total_cost: f64,
+    /// Number of hexes the search expanded
+    pub expanded_nodes: u32,
+    /// The path as straight runs, in order
+    pub segments: Vec<PathSegment>,
+}
+
+/// Part of a waypoint path between two consecutive stops
+#[napi(object)]
+pub struct PathLeg {
+    /// Index of the first point of the leg in the path
+    pub start_index: u32,
+    /// Index of the last point of the leg, the first point of the next leg
+    pub end_index: u32,
+    pub cost: f64,
+}
+
+#[napi(object)]
+pub struct WaypointPathResult {
+    pub path: PathResult,
+    /// One leg per waypoint and one for the target, fewer when the path is partial and none
+    /// when it is unreachable
+    pub legs: Vec<PathLeg>,
+}
+
+// Node positions of the path found by a search, empty when it is unreachable
+pub struct SearchOutcome {
+    pub status: PathStatus,
+    pub path: Vec<(usize, usize)>,
+    pub expanded_nodes: u32,
+}
+
+// Start and terminal nodes of a validated `PathRequest`, together with the node positions of
+// the requested target hexes the terminal nodes were derived from
+pub struct SearchEndpoints {
+    pub start_points: Vec<(i32, i32)>,
+    pub target_points: Vec<(i32, i32)>,
+    pub terminal_nodes: Vec<TempNode>,
+}
+
+// Error message of the searches that fail when no target can be reached, naming the requested
+// target hexes rather than every terminal node
+pub fn path_not_found_message(start_points: &[(i32, i32)], target_points: &[(i32, i32)]) -> String {
+    let starts: Vec<String> = start_points.iter().map(|(x, y)| format!("[{}, {}]", x, y)).collect();
+    let targets: Vec<String> = target_points.iter().map(|(x, y)| format!("[{}, {}]", x, y)).collect();
+    format!("Path not found from {} to {}", starts.join(", "), targets.join(", "))
+}
@@ -0,0 +1,128 @@
+use napi_derive::napi;
+
+use crate::temp_node::TempNode;
+use crate::SecurityIndexType;
+
+#[napi]
+#[derive(Debug, PartialEq)]
+pub enum BattlegroundPolicy {
+    /// Battleground hexes cost `battlegroundMultiplier` times their usual cost
+    Allow,
+    /// Battleground hexes are never entered
+    Forbid,
+}
+
+#[napi(object)]
+#[derive(Clone)]
+pub struct SecurityRule {
+    pub security_index: SecurityIndexType,
+    /// Factor applied to the cost of entering such hexes (default: 1)
+    pub multiplier: Option<f64>,
+    /// Such hexes are never entered (default: false)
+    pub banned: Option<bool>,
+}
+
+/// How a unit moves: entering a hex costs `multiplier / passability`, where the multiplier
+/// combines the rule of the hex's security index and the battleground multiplier. The start
+/// hex is never checked against the profile.
+#[napi(object)]
+#[derive(Clone, Default)]
+pub struct MovementProfile {
+    pub security_rules: Option<Vec<SecurityRule>>,
+    /// Defaults to `BattlegroundPolicy.Allow`
+    pub battleground: Option<BattlegroundPolicy>,
+    /// Default: 1
+    pub battleground_multiplier: Option<f64>,
+    /// Hexes with a lower passability are treated as impassable
+    pub min_passability: Option<f64>,
+}
+
+/// Attributes of a hex a movement profile reads besides its passability, parsed once when the
+/// grid is created.
+#[derive(Debug, Clone, Copy)]
+pub struct HexTerrain {
+    pub battleground: bool,
+    pub security_index: SecurityIndexType,
+}
+
+impl SecurityIndexType {
+    /// Parses the `security_index` of a hex. Unknown values are rejected, a misspelled index
+    /// would silently escape the rules of its type.
+    pub fn from_security_index(security_index: &str) -> Result<Self, String> {
+        match security_index {
+            "no_battles" => Ok(SecurityIndexType::NoBattles),
+            "safe" => Ok(SecurityIndexType::Safe),
+            "not_safe" => Ok(SecurityIndexType::NotSafe),
+            "free_pvp" => Ok(SecurityIndexType::FreePvP),
+            _ => Err(format!(
+                "Unknown security index \"{}\", expected one of no_battles, safe, not_safe or free_pvp",
+                security_index
+            )),
+        }
+    }
+}
+
+impl MovementProfile {
+    /// Cost of entering the node, infinite when the profile keeps the unit out of it.
+    pub fn step_cost(&self, node: &TempNode, terrain: &HexTerrain) -> f64 {
+        if !node.passable || node.passability < self.min_passability.unwrap_or(0.0) {
+            return f64::INFINITY;
+        }
+
+        let mut multiplier = 1.0;
+        if terrain.battleground {
+            if self.battleground == Some(BattlegroundPolicy::Forbid) {
+                return f64::INFINITY;
+            }
+            multiplier *= self.battleground_multiplier.unwrap_or(1.0);
+        }
+
+        if let Some(rule) = self.security_rules.iter().flatten().find(|rule| rule.security_index == terrain.security_index) {
+            if rule.banned.unwrap_or(false) {
+                return f64::INFINITY;
+            }
+            multiplier *= rule.multiplier.unwrap_or(1.0);
+        }
+
+        multiplier / node.passability
+    }
+
+    /// Smallest factor the profile can apply to a cost. The search heuristic is scaled by it,
+    /// so that it never overestimates when the profile makes some hexes cheaper.
+    pub fn min_multiplier(&self) -> f64 {
+        let rule_multiplier = self
+            .security_rules
+            .iter()
+            .flatten()
+            .filter(|rule| !rule.banned.unwrap_or(false))
+            .map(|rule| rule.multiplier.unwrap_or(1.0))
+            .fold(1.0, f64::min);
+        let battleground_multiplier = match self.battleground {
+            Some(BattlegroundPolicy::Forbid) => 1.0,
+            _ => self.battleground_multiplier.unwrap_or(1.0).min(1.0),
+        };
+
+        rule_multiplier.min(1.0) * battleground_multiplier
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let multipliers = self
+            .security_rules
+            .iter()
+            .flatten()
+            .filter_map(|rule| rule.multiplier)
+            .chain(self.battleground_multiplier);
+
+        for multiplier in multipliers {
+            if !(multiplier > 0.0 && multiplier.is_finite()) {
+                return Err(format!("Cost multiplier must be a positive number, got {}", multiplier));
+            }
+        }
+        if let Some(min_passability) = self.min_passability {
+            if !(min_passability >= 0.0 && min_passability.is_finite()) {
+                return Err(format!("Minimum passability must be a non-negative number, got {}", min_passability));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,288 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use crate::temp_search_grid::TempSearchGrid;
+
+type NodePoint = (usize, usize);
+
+// Planners a grid keeps at once, every one holds the region it explored until it is removed
+pub const MAX_PLANNERS: usize = 8192;
+
+// D* Lite priority, compared lexicographically
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Key(f64, f64);
+
+impl Key {
+    fn total_cmp(&self, other: &Key) -> Ordering {
+        self.0.total_cmp(&other.0).then(self.1.total_cmp(&other.1))
+    }
+}
+
+#[derive(Debug)]
+struct QueueEntry {
+    key: Key,
+    index: usize,
+}
+
+impl PartialEq for QueueEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueueEntry {}
+
+impl Ord for QueueEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed for a min-heap
+        other.key.total_cmp(&self.key).then(other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for QueueEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Persistent D* Lite search from a start node to a set of goal nodes.
+///
+/// The search runs backward from the goals, so `g` is the cost of reaching the closest goal.
+/// When the passability of a node changes only that node and its neighbors are queued again,
+/// and the next `compute_path` repairs the costs around them instead of searching from scratch.
+/// Moving the start keeps every cost and only offsets the queue keys by `k_m`.
+///
+/// Costs are kept only for the nodes the search has touched, a missing one is infinite, so a
+/// planner takes memory in proportion to the region it explored rather than to the grid.
+pub struct Planner {
+    width: usize,
+    start: NodePoint,
+    goals: HashSet<NodePoint>,
+    k_m: f64,
+    g: HashMap<NodePoint, f64>,
+    rhs: HashMap<NodePoint, f64>,
+    queue: BinaryHeap<QueueEntry>,
+    // Key each node is queued with, entries with another key are stale
+    queued_keys: HashMap<NodePoint, Key>,
+}
+
+impl Planner {
+    pub fn new<H>(grid: &TempSearchGrid, start: NodePoint, goals: &[NodePoint], heuristic: &H) -> Self
+    where
+        H: Fn(NodePoint, NodePoint) -> f64,
+    {
+        let mut planner = Planner {
+            width: grid.get_width(),
+            start,
+            goals: goals.iter().copied().collect(),
+            k_m: 0.0,
+            g: HashMap::new(),
+            rhs: HashMap::new(),
+            queue: BinaryHeap::new(),
+            queued_keys: HashMap::new(),
+        };
+
+        for &goal in goals {
+            planner.update_vertex(grid, goal, heuristic);
+        }
+        planner
+    }
+
+    pub fn move_start<H>(&mut self, start: NodePoint, heuristic: &H)
+    where
+        H: Fn(NodePoint, NodePoint) -> f64,
+    {
+        self.k_m += heuristic(self.start, start);
+        self.start = start;
+    }
+
+    /// Queues a node whose passability changed together with the neighbors whose costs depend on it.
+    pub fn notify_changed<H>(&mut self, grid: &TempSearchGrid, point: NodePoint, heuristic: &H)
+    where
+        H: Fn(NodePoint, NodePoint) -> f64,
+    {
+        self.update_vertex(grid, point, heuristic);
+        for neighbor in grid.get_passable_neighbors(point.0, point.1) {
+            self.update_vertex(grid, (neighbor.x, neighbor.y), heuristic);
+        }
+    }
+
+    /// Repairs the costs the start depends on and follows them to a goal. Returns the node path,
+    /// `None` when no goal is reachable, and the number of nodes expanded by the repair.
+    pub fn compute_path<H>(&mut self, grid: &TempSearchGrid, heuristic: &H) -> (Option<Vec<NodePoint>>, u32)
+    where
+        H: Fn(NodePoint, NodePoint) -> f64,
+    {
+        let expanded_nodes = self.compute_shortest_path(grid, heuristic);
+
+        let mut current = self.start;
+        if self.g(current).is_infinite() || !grid.get_node_at_point(current).passable {
+            return (None, expanded_nodes);
+        }
+
+        let len = grid.get_width() * grid.get_height();
+        let mut path = vec![current];
+        while !self.goals.contains(&current) {
+            let next = grid
+                .get_passable_neighbors(current.0, current.1)
+                .into_iter()
+                .map(|neighbor| ((neighbor.x, neighbor.y), 1.0 / neighbor.passability + self.g((neighbor.x, neighbor.y))))
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+
+            match next {
+                // A path can't be longer than the number of nodes
+                Some((point, cost)) if cost.is_finite() && path.len() < len => {
+                    path.push(point);
+                    current = point;
+                }
+                _ => return (None, expanded_nodes),
+            }
+        }
+        (Some(path), expanded_nodes)
+    }
+
+    fn index(&self, (x, y): NodePoint) -> usize {
+        y * self.width + x
+    }
+
+    fn g(&self, point: NodePoint) -> f64 {
+        self.g.get(&point).copied().unwrap_or(f64::INFINITY)
+    }
+
+    fn rhs(&self, point: NodePoint) -> f64 {
+        self.rhs.get(&point).copied().unwrap_or(f64::INFINITY)
+    }
+
+    // Stores a cost, dropping infinite ones so the maps only hold the explored region
+    fn set_cost(costs: &mut HashMap<NodePoint, f64>, point: NodePoint, cost: f64) {
+        if cost.is_finite() {
+            costs.insert(point, cost);
+        } else {
+            costs.remove(&point);
+        }
+    }
+
+    fn calculate_key<H>(&self, point: NodePoint, heuristic: &H) -> Key
+    where
+        H: Fn(NodePoint, NodePoint) -> f64,
+    {
+        let cost = self.g(point).min(self.rhs(point));
+        Key(cost + heuristic(self.start, point) + self.k_m, cost)
+    }
+
+    fn update_vertex<H>(&mut self, grid: &TempSearchGrid, point: NodePoint, heuristic: &H)
+    where
+        H: Fn(NodePoint, NodePoint) -> f64,
+    {
+        let rhs = if !grid.get_node_at_point(point).passable {
+            f64::INFINITY
+        } else if self.goals.contains(&point) {
+            0.0
+        } else {
+            grid.get_passable_neighbors(point.0, point.1)
+                .iter()
+                .map(|neighbor| 1.0 / neighbor.passability + self.g((neighbor.x, neighbor.y)))
+                .fold(f64::INFINITY, f64::min)
+        };
+        Self::set_cost(&mut self.rhs, point, rhs);
+
+        if self.g(point) != rhs {
+            let key = self.calculate_key(point, heuristic);
+            self.queued_keys.insert(point, key);
+            self.queue.push(QueueEntry { key, index: self.index(point) });
+        } else {
+            self.queued_keys.remove(&point);
+        }
+    }
+
+    // Smallest key in the queue, dropping stale entries on the way
+    fn top_key(&mut self) -> Option<Key> {
+        while let Some(entry) = self.queue.peek() {
+            let point = (entry.index % self.width, entry.index / self.width);
+            if self.queued_keys.get(&point) == Some(&entry.key) {
+                return Some(entry.key);
+            }
+            self.queue.pop();
+        }
+        None
+    }
+
+    fn compute_shortest_path<H>(&mut self, grid: &TempSearchGrid, heuristic: &H) -> u32
+    where
+        H: Fn(NodePoint, NodePoint) -> f64,
+    {
+        let mut expanded_nodes = 0;
+
+        while let Some(top_key) = self.top_key() {
+            let start_key = self.calculate_key(self.start, heuristic);
+            if top_key.total_cmp(&start_key) != Ordering::Less && self.rhs(self.start) == self.g(self.start) {
+                break;
+            }
+
+            let index = self.queue.pop().unwrap().index;
+            let point = (index % self.width, index / self.width);
+            self.queued_keys.remove(&point);
+            expanded_nodes += 1;
+
+            let new_key = self.calculate_key(point, heuristic);
+            if top_key.total_cmp(&new_key) == Ordering::Less {
+                self.queued_keys.insert(point, new_key);
+                self.queue.push(QueueEntry { key: new_key, index });
+                continue;
+            }
+
+            let rhs = self.rhs(point);
+            if self.g(point) > rhs {
+                Self::set_cost(&mut self.g, point, rhs);
+            } else {
+                self.g.remove(&point);
+                self.update_vertex(grid, point, heuristic);
+            }
+            for neighbor in grid.get_passable_neighbors(point.0, point.1) {
+                self.update_vertex(grid, (neighbor.x, neighbor.y), heuristic);
+            }
+        }
+        expanded_nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_grid::{assert_cost_eq, Lcg, RandomGrid};
+
+    #[test]
+    fn repaired_costs_match_astar() {
+        let mut rng = Lcg::new(67);
+        for case in 0..40 {
+            let mut grid = RandomGrid::new(&mut rng);
+            let (Some(mut start), Some(goal)) = (grid.random_passable_node(&mut rng), grid.random_passable_node(&mut rng)) else {
+                continue;
+            };
+            let geometry = grid.geometry;
+            let heuristic = move |from: NodePoint, to: NodePoint| geometry.heuristic(from.0, from.1, &[geometry.node_cube(to.0, to.1)]);
+            let mut planner = Planner::new(&grid.grid, start, &[goal], &heuristic);
+
+            for change in 0..25 {
+                let (path, _) = planner.compute_path(&grid.grid, &heuristic);
+                let context = format!("case {} change {} from {:?} to {:?}", case, change, start, goal);
+                let expected = if grid.grid.get_node_at_point(start).passable { grid.astar_cost(&[start], &[goal]) } else { None };
+                assert_cost_eq(path.as_ref().map(|path| grid.path_cost(path)), expected, &context);
+
+                if let Some(path) = &path {
+                    assert_eq!((path[0], path[path.len() - 1]), (start, goal), "{}", context);
+                    // Walk along the path now and then, as a unit following it would
+                    if path.len() > 1 && rng.below(3) == 0 {
+                        start = path[1];
+                        planner.move_start(start, &heuristic);
+                    }
+                }
+
+                let point = grid.random_node(&mut rng);
+                let passability = rng.passability();
+                grid.set_passability(point, passability);
+                planner.notify_changed(&grid.grid, point, &heuristic);
+            }
+        }
+    }
+}
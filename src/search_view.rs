@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::coords::CubeCoord;
+use crate::hierarchy::Hierarchy;
+use crate::path::{PathOptions, PathResult, SearchAlgorithm, SearchEndpoints, SearchOutcome};
+use crate::temp_search_grid::TempSearchGrid;
+use crate::{astar, bidirectional, smoothing, HexTerrain, MovementProfile, Point};
+
+/// Placement of the search grid in world coordinates.
+#[derive(Debug, Clone, Copy)]
+pub struct GridGeometry {
+    pub min_x: i32,
+    pub min_y: i32,
+    pub width: i32,
+    pub odd_increment: i32,
+    pub wrap_x: bool,
+}
+
+impl GridGeometry {
+    // On a wrapped grid returns the copy of `cube`, shifted by whole grid widths, that is closest
+    // to `from`. Shifting by an even width keeps the column parity, so the copy is a valid hex.
+    pub fn nearest_wrapped_cube(&self, from: CubeCoord, cube: CubeCoord) -> CubeCoord {
+        if !self.wrap_x {
+            return cube;
+        }
+
+        let shift = CubeCoord::new(self.width, -self.width / 2);
+        let laps = (from.q - cube.q).div_euclid(self.width);
+        let candidate = cube + shift * laps;
+
+        if from.distance(candidate + shift) < from.distance(candidate) {
+            candidate + shift
+        } else {
+            candidate
+        }
+    }
+
+    pub fn node_cube(&self, x: usize, y: usize) -> CubeCoord {
+        CubeCoord::from_offset(x as i32, y as i32, self.odd_increment)
+    }
+
+    // Distance to the closest terminal node, which keeps the heuristic admissible for any
+    // number of targets
+    pub fn heuristic(&self, x: usize, y: usize, terminal_cubes: &[CubeCoord]) -> f64 {
+        let start = self.node_cube(x, y);
+        terminal_cubes
+            .iter()
+            .map(|&end| start.distance(self.nearest_wrapped_cube(start, end)))
+            .min()
+            .unwrap_or(0) as f64
+    }
+}
+
+/// Everything a path search reads. Clones share the search grid and the hierarchy, so a clone
+/// can search on another thread while the `HexGrid` it came from keeps changing: the grid copies
+/// them before its first change instead.
+#[derive(Clone)]
+pub struct SearchView {
+    pub geometry: GridGeometry,
+    pub grid: Arc<TempSearchGrid>,
+    pub hierarchy: Option<Arc<Hierarchy>>,
+    /// Terrain the movement profile reads, indexed by hex id
+    pub terrain: Arc<[HexTerrain]>,
+    /// Without a movement profile entering a node costs `1 / passability`
+    pub profile: Option<Arc<MovementProfile>>,
+    /// Factors applied to the cost of entering some nodes, a missing node keeps its cost
+    pub penalties: Option<Arc<HashMap<(usize, usize), f64>>>,
+    /// Factor keeping the heuristic admissible when the profile makes some hexes cheaper
+    pub heuristic_scale: f64,
+}
+
+impl SearchView {
+    /// Runs the algorithm selected by `options`, a hierarchical search needs the view to have a
+    /// hierarchy. Straightens the path when `options` ask for it.
+    pub fn search(&self, endpoints: &SearchEndpoints, options: &PathOptions) -> SearchOutcome {
+        let start_points: Vec<(usize, usize)> = endpoints.start_points.iter().map(|&(x, y)| (x as usize, y as usize)).collect();
+        let terminal_points: Vec<(usize, usize)> = endpoints.terminal_nodes.iter().map(|n| (n.x, n.y)).collect();
+        let terminal_cubes: Vec<CubeCoord> = terminal_points.iter().map(|&(x, y)| self.geometry.node_cube(x, y)).collect();
+        let heuristic = |x, y| self.geometry.heuristic(x, y, &terminal_cubes) * self.heuristic_scale;
+        let step_cost = |x, y| self.step_cost(x, y);
+
+        let mut outcome = match (options.algorithm, &self.hierarchy) {
+            // The abstract graph is built for plain passability costs, profiles and penalties fall back to A*
+            (Some(SearchAlgorithm::Hierarchical), Some(hierarchy)) if self.profile.is_none() && self.penalties.is_none() => {
+                hierarchy.search(&self.grid, &start_points, &terminal_points, options, heuristic)
+            }
+            (Some(SearchAlgorithm::Bidirectional), _) => {
+                let start_cubes: Vec<CubeCoord> = start_points.iter().map(|&(x, y)| self.geometry.node_cube(x, y)).collect();
+                bidirectional::search(&self.grid, &start_points, &terminal_points, options, step_cost, heuristic, |x, y| {
+                    self.geometry.heuristic(x, y, &start_cubes) * self.heuristic_scale
+                })
+            }
+            _ => astar::search(&self.grid, &start_points, &terminal_points, options, step_cost, heuristic),
+        };
+
+        if options.smooth.unwrap_or(false) {
+            outcome.path = smoothing::straighten(self, outcome.path);
+        }
+        outcome
+    }
+
+    /// Cost of entering the node, infinite when the movement profile keeps units out of it.
+    pub fn step_cost(&self, x: usize, y: usize) -> f64 {
+        let node = self.grid.get_node_at_point((x, y));
+        let cost = match &self.profile {
+            Some(profile) => profile.step_cost(node, &self.terrain[y * self.grid.get_width() + x]),
+            // A negative passability also makes a hex impassable
+            None if !node.passable => f64::INFINITY,
+            None => 1.0 / node.passability,
+        };
+        match self.penalties.as_ref().and_then(|penalties| penalties.get(&(x, y))) {
+            Some(penalty) => cost * penalty,
+            None => cost,
+        }
+    }
+
+    pub fn build_result(&self, outcome: SearchOutcome) -> PathResult {
+        let path = outcome.path;
+        let mut points = Vec::with_capacity(path.len());
+        let mut hex_ids = Vec::with_capacity(path.len());
+        let mut costs = Vec::with_capacity(path.len());
+        let mut total_cost = 0.0;
+
+        for (index, &(x, y)) in path.iter().enumerate() {
+            // Entering a hex costs the same as in the search, the start hex is free
+            if index > 0 {
+                total_cost += self.step_cost(x, y);
+            }
+
+            points.push(self.to_point(x, y));
+            hex_ids.push((y * self.grid.get_width() + x) as u32);
+            costs.push(total_cost);
+        }
+
+        let segments = smoothing::segments(self, &path);
+
+        PathResult {
+            status: outcome.status,
+            points,
+            hex_ids,
+            costs,
+            total_cost,
+            expanded_nodes: outcome.expanded_nodes,
+            segments,
+        }
+    }
+
+    /// Node of the hex, wrapped onto the grid on a wrapped one. `None` outside the grid.
+    pub fn cube_node(&self, cube: CubeCoord) -> Option<(usize, usize)> {
+        let (mut x, y) = cube.to_offset(self.geometry.odd_increment);
+        if self.geometry.wrap_x {
+            x = x.rem_euclid(self.geometry.width);
+        }
+        if x < 0 || y < 0 || !self.grid.is_node_inside(x as usize, y as usize) {
+            return None;
+        }
+        Some((x as usize, y as usize))
+    }
+
+    pub fn to_point(&self, x: usize, y: usize) -> Point {
+        Point { x: x as i32 + self.geometry.min_x, y: y as i32 + self.geometry.min_y }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn geometry(wrap_x: bool) -> GridGeometry {
+        GridGeometry { min_x: 0, min_y: 0, width: 8, odd_increment: 0, wrap_x }
+    }
+
+    #[test]
+    fn nearest_wrapped_cube_crosses_the_seam() {
+        let geometry = geometry(true);
+        let left = CubeCoord::from_offset(0, 0, 0);
+        let right = CubeCoord::from_offset(7, 0, 0);
+
+        // The copy of the last column sits left of the first one and the other way around
+        assert_eq!(geometry.nearest_wrapped_cube(left, right), CubeCoord::from_offset(-1, 0, 0));
+        assert_eq!(geometry.nearest_wrapped_cube(right, left), CubeCoord::from_offset(8, 0, 0));
+        assert_eq!(left.distance(geometry.nearest_wrapped_cube(left, right)), 1);
+
+        // A hex closer without wrapping is kept as it is
+        let near = CubeCoord::from_offset(3, 2, 0);
+        assert_eq!(geometry.nearest_wrapped_cube(left, near), near);
+    }
+
+    #[test]
+    fn nearest_wrapped_cube_ignores_laps() {
+        let geometry = geometry(true);
+        let shift = CubeCoord::new(8, -4);
+        let from = CubeCoord::from_offset(1, 3, 0);
+        let cube = CubeCoord::from_offset(6, 1, 0);
+        let nearest = geometry.nearest_wrapped_cube(from, cube);
+
+        for laps in -3..=3 {
+            assert_eq!(geometry.nearest_wrapped_cube(from, cube + shift * laps), nearest);
+        }
+    }
+
+    #[test]
+    fn nearest_wrapped_cube_is_the_closest_copy() {
+        let geometry = geometry(true);
+        let shift = CubeCoord::new(8, -4);
+        for from in (0..48).map(|index| CubeCoord::from_offset(index % 8, index / 8, 0)) {
+            for cube in (0..48).map(|index| CubeCoord::from_offset(index % 8, index / 8, 0)) {
+                let closest = (-2..=2).map(|laps| from.distance(cube + shift * laps)).min().unwrap();
+                assert_eq!(from.distance(geometry.nearest_wrapped_cube(from, cube)), closest);
+            }
+        }
+    }
+
+    #[test]
+    fn nearest_wrapped_cube_without_wrapping() {
+        let geometry = geometry(false);
+        let cube = CubeCoord::from_offset(7, 0, 0);
+        assert_eq!(geometry.nearest_wrapped_cube(CubeCoord::from_offset(0, 0, 0), cube), cube);
+    }
+}
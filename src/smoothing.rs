@@ -0,0 +1,82 @@
+use crate::coords::{CubeCoord, CUBE_DIRECTIONS};
+use crate::path::PathSegment;
+use crate::search_view::SearchView;
+
+// Tolerance for comparing sums of the same step costs taken in a different order
+const COST_EPSILON: f64 = 1e-9;
+
+/// Replaces parts of the path by straight hex lines. From every hex it jumps to the farthest
+/// later hex whose line takes as many steps, enters only hexes the search could enter and costs
+/// no more, so the path keeps its length and never gets more expensive.
+pub fn straighten(view: &SearchView, path: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    if path.len() < 3 {
+        return path;
+    }
+
+    let cubes: Vec<CubeCoord> = path.iter().map(|&(x, y)| view.geometry.node_cube(x, y)).collect();
+    let mut straightened = vec![path[0]];
+    let mut anchor = 0;
+
+    while anchor < path.len() - 1 {
+        let mut best = (anchor + 1, vec![path[anchor + 1]]);
+        let mut path_cost = 0.0;
+
+        for end in anchor + 1..path.len() {
+            path_cost += view.step_cost(path[end].0, path[end].1);
+
+            // A part that is not a shortest walk can't be replaced, neither can any longer one
+            let end_cube = view.geometry.nearest_wrapped_cube(cubes[anchor], cubes[end]);
+            if cubes[anchor].distance(end_cube) != (end - anchor) as i32 {
+                break;
+            }
+
+            if let Some(line) = line_nodes(view, cubes[anchor], end_cube) {
+                let line_cost: f64 = line.iter().map(|&(x, y)| view.step_cost(x, y)).sum();
+                if line_cost <= path_cost + COST_EPSILON {
+                    best = (end, line);
+                }
+            }
+        }
+
+        straightened.extend(best.1);
+        anchor = best.0;
+    }
+
+    straightened
+}
+
+// Nodes of the hex line after `from`, `None` when it leaves the grid or enters a node the
+// search can't enter
+fn line_nodes(view: &SearchView, from: CubeCoord, to: CubeCoord) -> Option<Vec<(usize, usize)>> {
+    from.line_to(to)
+        .into_iter()
+        .skip(1)
+        .map(|cube| {
+            let node = view.cube_node(cube)?;
+            view.step_cost(node.0, node.1).is_finite().then_some(node)
+        })
+        .collect()
+}
+
+/// Splits the path into runs of steps going in the same direction.
+pub fn segments(view: &SearchView, path: &[(usize, usize)]) -> Vec<PathSegment> {
+    let mut segments: Vec<PathSegment> = Vec::new();
+
+    for (index, step) in path.windows(2).enumerate() {
+        let from = view.geometry.node_cube(step[0].0, step[0].1);
+        let to = view.geometry.nearest_wrapped_cube(from, view.geometry.node_cube(step[1].0, step[1].1));
+        let Some(direction) = CUBE_DIRECTIONS.iter().position(|&direction| direction == to - from) else {
+            continue;
+        };
+        let direction = direction as u32;
+
+        match segments.last_mut() {
+            Some(segment) if segment.direction == direction && segment.end_index == index as u32 => {
+                segment.end_index += 1;
+            }
+            _ => segments.push(PathSegment { start_index: index as u32, end_index: index as u32 + 1, direction }),
+        }
+    }
+
+    segments
+}
@@ -1,176 +1,77 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
+use std::sync::Arc;
 use crate::temp_node::TempNode;
 
+// Neighbor offsets in the order they are listed, for columns with an even (x + odd_increment)
+// and for the shoved down odd ones
+const EVEN_COLUMN_OFFSETS: [(i64, i64); 6] = [(0, -1), (0, 1), (1, -1), (-1, -1), (1, 0), (-1, 0)];
+const ODD_COLUMN_OFFSETS: [(i64, i64); 6] = [(0, -1), (0, 1), (1, 0), (1, 1), (-1, 1), (-1, 0)];
+
+// Nodes per shared chunk, a change copies only its own chunk when a search holds the grid
+const CHUNK_LEN: usize = 4096;
+
+/// Passability of every node, stored in chunks of `CHUNK_LEN` nodes indexed by `y * width + x`.
+/// Clones share the chunks, a clone that changes a node copies only the chunk holding it.
+#[derive(Clone)]
 pub struct TempSearchGrid {
     width: usize,
     height: usize,
     odd_increment: usize,
-    nodes: Vec<Vec<TempNode>>,
-    neighbor_node_cache: HashMap<(usize, usize), Vec<(usize, usize)>>,
-    neighbor_passable_nodes_cache: HashMap<(usize, usize), Vec<(usize, usize)>>,
+    wrap_x: bool,
+    chunks: Vec<Arc<Vec<TempNode>>>,
 }
 
 impl TempSearchGrid {
-    pub fn new(width: usize, height: usize, odd_increment: usize) -> Self {
-        let mut grid = Self {
-            width,
-            height,
-            odd_increment,
-            nodes: Vec::new(),
-            neighbor_node_cache: HashMap::new(),
-            neighbor_passable_nodes_cache: HashMap::new(),
-        };
-    
-        // Now build the nodes using the instance method
-        grid.nodes = grid.build_nodes();
-        grid
-    }
+    pub fn new(width: usize, height: usize, odd_increment: usize, wrap_x: bool) -> Self {
+        let len = width * height;
+        let chunks = (0..len)
+            .step_by(CHUNK_LEN)
+            .map(|start| Arc::new((start..len.min(start + CHUNK_LEN)).map(|index| TempNode::new(index % width, index / width, true, 1.0)).collect()))
+            .collect();
 
-    pub fn build_nodes(&mut self) -> Vec<Vec<TempNode>> {
-        let mut nodes = Vec::with_capacity(self.height);
-    
-        for i in 0..self.height {
-            let mut row = Vec::with_capacity(self.width);
-            for j in 0..self.width {
-                let node = TempNode::new(j, i, true, 1.0);
-                row.push(node);
-            }
-            nodes.push(row); 
-        }
-    
-        nodes
+        Self { width, height, odd_increment, wrap_x, chunks }
     }
 
-
-    pub fn compute_neighbor_nodes_cache(&mut self) -> Result<(), String> {
-        for node_row in &self.nodes {
-            for node in node_row {
-                // Get the neighbors for the current node
-                let neighbors = self.get_neighbor_nodes(node);
-                let coords: Vec<(usize, usize)> = neighbors
-                .iter()
-                .map(|node| (node.x as usize, node.y as usize))
-                .collect();
-                self.neighbor_node_cache.insert((node.x.try_into().unwrap(), node.y.try_into().unwrap()), coords);
-
-                // Get the passable neighbors for the current node
-                let neighbors_passable_nodes = self.get_neighbors_passable_nodes(node);
-                let neighbors_coords: Vec<(usize, usize)> = neighbors_passable_nodes
-                .iter()
-                .map(|node| (node.x as usize, node.y as usize))
-                .collect();
-                self.neighbor_passable_nodes_cache.insert((node.x.try_into().expect("Can not insert cache"), node.y.try_into().unwrap()), neighbors_coords);
-
-            }
-        }
-        Ok(())
+    pub fn get_width(&self) -> usize {
+        self.width
     }
 
-    pub fn recheck_node_passable(&mut self, x: usize, y: usize) -> Result<(), String> {
-        let node  = self.get_node_at_point((x, y));
-
-        let neighbors = self.get_neighbor_nodes(node);
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
 
-        let neighbor_passable_nodes = self.get_neighbors_passable_nodes(node)
-        .iter()
-        .map(|node| (node.x as usize, node.y as usize))
-        .collect::<Vec<(usize, usize)>>();
-        self.neighbor_passable_nodes_cache.insert((node.x, node.y), neighbor_passable_nodes);
-    
-        for neighbor in neighbors {
-            let neighbor_neighbors = self.get_neighbors_passable_nodes(&neighbor)
-            .iter()
-            .filter(|&&TempNode { passable, .. }| *passable)
-            .map(|&TempNode { x, y, .. }| (*x as usize, *y as usize))
-            .collect::<Vec<(usize, usize)>>();
-        self.neighbor_passable_nodes_cache.insert((neighbor.x, neighbor.y), neighbor_neighbors);
+    fn node_mut(&mut self, x: usize, y: usize) -> Result<&mut TempNode, String> {
+        if !self.is_node_inside(x, y) {
+            return Err(format!("Invalid node coordinates: ({}, {})", x, y));
         }
-        Ok(())
+        let index = y * self.width + x;
+        Ok(&mut Arc::make_mut(&mut self.chunks[index / CHUNK_LEN])[index % CHUNK_LEN])
     }
 
     pub fn set_node_passable(&mut self, x: usize, y: usize, passable: bool) -> Result<(), String> {
-        if let Some(node) = self.nodes.get_mut(y).and_then(|row| row.get_mut(x)) {
-            node.set_passable(passable);
-            Ok(())
-        } else {
-            Err(format!("Invalid node coordinates: ({}, {})", x, y))
-        }
+        self.node_mut(x, y)?.set_passable(passable);
+        Ok(())
     }
 
     pub fn set_node_passability(&mut self, x: usize, y: usize, passability: f64) -> Result<(), String> {
-        if let Some(node) = self.nodes.get_mut(y).and_then(|row| row.get_mut(x)) {
-            node.set_passability(passability);
-            Ok(())
-        } else {
-            Err(format!("Invalid node coordinates: ({}, {})", x, y))
-        }
-    }
-    pub fn set_node_closed(&mut self, x: usize, y: usize, v: bool) -> Result<(), String> {
-        if let Some(node) = self.nodes.get_mut(y).and_then(|row| row.get_mut(x)) {
-            node.set_closed(v);
-            Ok(())
-        } else {
-            Err(format!("Invalid node coordinates: ({}, {})", x, y))
-        }
-    }
-
-    pub fn set_node_h(&mut self, x: usize, y: usize, v: f64) -> Result<(), String> {
-        if let Some(node) = self.nodes.get_mut(y).and_then(|row| row.get_mut(x)) {
-            node.set_h(v);
-            Ok(())
-        } else {
-            Err(format!("Invalid node coordinates: ({}, {})", x, y))
-        }
-    }
-
-    pub fn update_node<F>(&mut self, x: usize, y: usize, mut f: F)
-    where
-        F: FnMut(&mut TempNode),
-    {
-        if let Some(row) = self.nodes.get_mut(y) {
-            if let Some(node) = row.get_mut(x) {
-                f(node);
-            }
-        }
+        self.node_mut(x, y)?.set_passability(passability);
+        Ok(())
     }
-    // pub fn get_neighbor_nodes_from_cache(&self, node: &TempNode) -> Result<Vec<TempNode>, String> {
-    //     // Look up the neighbors from the cache using the node's coordinates
-    //     let neighbors_coords = self.neighbor_node_cache
-    //         .get(&(node.x.try_into().unwrap(), node.y.try_into().unwrap())) // Fetch neighbors' coordinates using the (x, y) tuple as the key
-    //         .ok_or_else(|| format!("Neighbor cache is empty for node at ({}, {})", node.x, node.y))?;
 
-    //     // Map coordinates to actual TempNode instances
-    //     let neighbors: Vec<TempNode> = neighbors_coords
-    //         .iter()
-    //         .map(|&(nx, ny)| *self.get_node_at_point((nx.try_into().unwrap(), ny.try_into().unwrap())))
-    //         .collect();
-
-    //     Ok(neighbors)
-    // }
-
-    pub fn get_neighbors_passable_nodes_from_cache(&self, x: usize, y: usize) -> Result<Vec<TempNode>, String> {
-        // Get the coordinates of passable neighbors from the cache
-        let neighbors_coords = self.neighbor_passable_nodes_cache
-            .get(&(x, y))
-            .ok_or_else(|| format!("Neighbor passable nodes cache is empty for ({}, {})", x, y))?;
-
-        let neighbors: Vec<TempNode> = neighbors_coords
-            .iter()
-            .map(|&(nx, ny)| *self.get_node_at_point((nx, ny))) // Convert coordinates into TempNode
-            .collect();
-    
-        Ok(neighbors)
+    /// Passable neighbors of (x, y), whether (x, y) itself is passable or not.
+    pub fn get_passable_neighbors(&self, x: usize, y: usize) -> Vec<TempNode> {
+        self.get_neighbor_points(x, y)
+            .into_iter()
+            .map(|point| *self.get_node_at_point(point))
+            .filter(|neighbor| neighbor.passable)
+            .collect()
     }
 
     pub fn is_node_on_border_of_impassable_area(&self, x: usize, y: usize) -> bool {
-        let neighbors = self.get_neighbors_passable_nodes_from_cache(x, y).unwrap_or(Vec::new());
-        if neighbors.len() > 0 {
-            return true;
-        }
-        false
+        !self.get_passable_neighbors(x, y).is_empty()
     }
 
-    pub fn get_border_passable_neighbors(&self, target_x: usize, target_y: usize) -> Result<Vec<(usize, usize)>, String> {
+    pub fn get_border_passable_neighbors(&self, target_x: usize, target_y: usize) -> Vec<(usize, usize)> {
         let mut open_neighbors = HashSet::new();
         let mut closed_neighbors = HashSet::new();
         let mut done_nodes = HashSet::new();
@@ -181,12 +82,8 @@ impl TempSearchGrid {
             let current = closed_neighbors.iter().cloned().next().unwrap();
             closed_neighbors.remove(&current);
 
-            let neighbors = self.neighbor_node_cache
-                .get(&current)
-                .ok_or_else(|| format!("Cache empty for {:?}", current))?;
-
-            for &neighbor in neighbors {
-                if self.nodes[neighbor.1][neighbor.0].passable {
+            for neighbor in self.get_neighbor_points(current.0, current.1) {
+                if self.get_node_at_point(neighbor).passable {
                     open_neighbors.insert(neighbor);
                 } else if !done_nodes.contains(&neighbor) {
                     closed_neighbors.insert(neighbor);
@@ -195,123 +92,47 @@ impl TempSearchGrid {
             done_nodes.insert(current);
         }
 
-        Ok(open_neighbors.into_iter().collect())
-    }
-
-    fn is_node_passable(&self, x: usize, y: usize) -> bool {
-        if self.is_node_inside(x, y) {
-            let node  = self.get_node_at_point((x.try_into().unwrap(), y.try_into().unwrap()));
-            node.passable
-        } else {
-            false
-        }
+        let mut open_neighbors: Vec<(usize, usize)> = open_neighbors.into_iter().collect();
+        open_neighbors.sort_unstable();
+        open_neighbors
     }
 
     pub fn is_node_inside(&self, x: usize, y: usize) -> bool {
         x < self.width && y < self.height
-   }
-
-    pub fn get_neighbors_passable_nodes(&self, node: &TempNode) -> Vec<&TempNode> {
-        let mut neighbors = Vec::new();
-        let x = node.x as usize;
-        let y = node.y as usize;
+    }
 
-        if self.is_node_passable(x, y - 1) {
-            neighbors.push(self.get_node_at_point((node.x, node.y - 1)));
-        }
-        if self.is_node_passable(x, y + 1) {
-            neighbors.push(self.get_node_at_point((node.x, node.y + 1)));
-        }
-        if (x + self.odd_increment) % 2 == 0 {
-            if self.is_node_passable(x + 1, y - 1) {
-                neighbors.push(self.get_node_at_point((node.x + 1, node.y - 1)));
-            }
-            if self.is_node_passable(x - 1, y - 1) {
-                neighbors.push(self.get_node_at_point((node.x - 1, node.y - 1)));
-            }
-            if self.is_node_passable(x + 1, y) {
-                neighbors.push(self.get_node_at_point((node.x + 1, node.y)));
-            }
-            if self.is_node_passable(x - 1, y) {
-                neighbors.push(self.get_node_at_point((node.x - 1, node.y)));
-            }
+    // Coordinates of the neighbors of (x, y) that lie on the grid. With wrap_x the first and
+    // the last column are adjacent
+    fn get_neighbor_points(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let offsets = if (x + self.odd_increment) % 2 == 0 {
+            &EVEN_COLUMN_OFFSETS
         } else {
-            if self.is_node_passable(x + 1, y) {
-                neighbors.push(self.get_node_at_point((node.x + 1, node.y)));
-            }
-            if self.is_node_passable(x + 1, y + 1) {
-                neighbors.push(self.get_node_at_point((node.x + 1, node.y + 1)));
-            }
-            if self.is_node_passable(x - 1, y + 1) {
-                neighbors.push(self.get_node_at_point((node.x - 1, node.y + 1)));
-            }
-            if self.is_node_passable(x - 1, y) {
-                neighbors.push(self.get_node_at_point((node.x -1, node.y)));
-            }
-        }
-
-        neighbors
-    }
-    
+            &ODD_COLUMN_OFFSETS
+        };
 
-    pub fn get_neighbor_nodes(&self, node: &TempNode) -> Vec<TempNode> {
-        let mut neighbors = Vec::new();
-        let x = node.x as usize;
-        let y = node.y as usize;
+        let mut points = Vec::with_capacity(6);
+        for &(dx, dy) in offsets {
+            let mut nx = x as i64 + dx;
+            let ny = y as i64 + dy;
 
-        if self.is_node_inside(x, y - 1) {
-            neighbors.push(*self.get_node_at_point((node.x, node.y - 1)));
-        }
-        if self.is_node_inside(x, y + 1) {
-            neighbors.push(*self.get_node_at_point((node.x, node.y + 1)));
-        }
-        if (x + self.odd_increment) % 2 == 0 {
-            if self.is_node_inside(x + 1, y - 1) {
-                neighbors.push(*self.get_node_at_point((node.x + 1, node.y - 1)));
-            }
-            if self.is_node_inside(x - 1, y - 1) {
-                neighbors.push(*self.get_node_at_point((node.x - 1, node.y - 1)));
-            }
-            if self.is_node_inside(x + 1, y) {
-                neighbors.push(*self.get_node_at_point((node.x + 1, node.y)));
-            }
-            if self.is_node_inside(x - 1, y) {
-                neighbors.push(*self.get_node_at_point((node.x - 1, node.y)));
-            }
-        } else {
-            if self.is_node_inside(x + 1, y) {
-                neighbors.push(*self.get_node_at_point((node.x + 1, node.y)));
-            }
-            if self.is_node_inside(x + 1, y + 1) {
-                neighbors.push(*self.get_node_at_point((node.x + 1, node.y + 1)));
+            if self.wrap_x {
+                nx = nx.rem_euclid(self.width as i64);
             }
-            if self.is_node_inside(x - 1, y + 1) {
-                neighbors.push(*self.get_node_at_point((node.x - 1, node.y + 1)));
+            if nx < 0 || ny < 0 || !self.is_node_inside(nx as usize, ny as usize) {
+                continue;
             }
-            if self.is_node_inside(x - 1, y) {
-                neighbors.push(*self.get_node_at_point((node.x - 1, node.y)));
+
+            let point = (nx as usize, ny as usize);
+            // Narrow wrapped grids reach the same column from both sides
+            if point != (x, y) && !points.contains(&point) {
+                points.push(point);
             }
         }
-        neighbors
+        points
     }
 
     pub fn get_node_at_point(&self, point: (usize, usize)) -> &TempNode {
-        let (x, y) = (point.0, point.1);
-        &self.nodes[y][x]
-    }
-
-    pub fn get_node_g_at_point(&self, point: (usize, usize)) -> &f64 {
-        let (x, y) = (point.0, point.1);
-        &self.nodes[y][x].g
-    }
-
-    // Method to reset all nodes in the grid
-    pub fn reset(&mut self) {
-        // Iterate over every node in the grid and reset it
-        for row in &mut self.nodes {
-            for node in row {
-                node.reset(); // Call the reset method on each TempNode
-            }
-        }
+        let index = point.1 * self.width + point.0;
+        &self.chunks[index / CHUNK_LEN][index % CHUNK_LEN]
     }
 }
@@ -0,0 +1,116 @@
+// Random search grids shared by the tests of the search algorithms
+
+use crate::astar;
+use crate::coords::CubeCoord;
+use crate::path::{PathOptions, PathStatus};
+use crate::search_view::GridGeometry;
+use crate::temp_search_grid::TempSearchGrid;
+
+type NodePoint = (usize, usize);
+
+// Linear congruential generator, enough to vary the grids without pulling in a dependency
+pub struct Lcg(u64);
+
+impl Lcg {
+    pub fn new(seed: u64) -> Self {
+        Lcg(seed)
+    }
+
+    pub fn next_f64(&mut self) -> f64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_f64() * n as f64) as usize
+    }
+
+    // Impassable one time in five, otherwise a full or a halved passability
+    pub fn passability(&mut self) -> f64 {
+        match self.below(5) {
+            0 => 0.0,
+            1 => 0.5,
+            _ => 1.0,
+        }
+    }
+}
+
+pub struct RandomGrid {
+    pub grid: TempSearchGrid,
+    pub geometry: GridGeometry,
+}
+
+impl RandomGrid {
+    // Grid of a random size, parity and wrapping, filled with random passabilities
+    pub fn new(rng: &mut Lcg) -> Self {
+        let width = 2 * (2 + rng.below(12));
+        let height = 2 + rng.below(20);
+        let odd_increment = rng.below(2);
+        let wrap_x = rng.below(3) == 0;
+
+        let mut grid = TempSearchGrid::new(width, height, odd_increment, wrap_x);
+        for y in 0..height {
+            for x in 0..width {
+                let passability = rng.passability();
+                grid.set_node_passable(x, y, passability > 0.0).unwrap();
+                grid.set_node_passability(x, y, passability).unwrap();
+            }
+        }
+
+        let geometry = GridGeometry { min_x: 0, min_y: 0, width: width as i32, odd_increment: odd_increment as i32, wrap_x };
+        RandomGrid { grid, geometry }
+    }
+
+    // Changes the passability of a node the way `HexGrid::set_hex_passability` does
+    pub fn set_passability(&mut self, (x, y): NodePoint, passability: f64) {
+        self.grid.set_node_passable(x, y, passability > 0.0).unwrap();
+        self.grid.set_node_passability(x, y, passability).unwrap();
+    }
+
+    pub fn random_node(&self, rng: &mut Lcg) -> NodePoint {
+        (rng.below(self.grid.get_width()), rng.below(self.grid.get_height()))
+    }
+
+    pub fn random_passable_node(&self, rng: &mut Lcg) -> Option<NodePoint> {
+        let passable: Vec<NodePoint> = (0..self.grid.get_height())
+            .flat_map(|y| (0..self.grid.get_width()).map(move |x| (x, y)))
+            .filter(|&point| self.grid.get_node_at_point(point).passable)
+            .collect();
+        (!passable.is_empty()).then(|| passable[rng.below(passable.len())])
+    }
+
+    pub fn step_cost(&self, x: usize, y: usize) -> f64 {
+        1.0 / self.grid.get_node_at_point((x, y)).passability
+    }
+
+    pub fn heuristic(&self, x: usize, y: usize, points: &[NodePoint]) -> f64 {
+        let cubes: Vec<CubeCoord> = points.iter().map(|&(x, y)| self.geometry.node_cube(x, y)).collect();
+        self.geometry.heuristic(x, y, &cubes)
+    }
+
+    // Sum of the costs of entering every node after the first one
+    pub fn path_cost(&self, path: &[NodePoint]) -> f64 {
+        path.iter().skip(1).map(|&(x, y)| self.step_cost(x, y)).sum()
+    }
+
+    // Cost of the A* path to the closest terminal, `None` when none is reachable
+    pub fn astar_cost(&self, start_points: &[NodePoint], terminal_points: &[NodePoint]) -> Option<f64> {
+        let outcome = astar::search(
+            &self.grid,
+            start_points,
+            terminal_points,
+            &PathOptions::default(),
+            |x, y| self.step_cost(x, y),
+            |x, y| self.heuristic(x, y, terminal_points),
+        );
+        (outcome.status == PathStatus::Found).then(|| self.path_cost(&outcome.path))
+    }
+}
+
+// Costs compared across algorithms are sums of the same steps in a different order
+pub fn assert_cost_eq(a: Option<f64>, b: Option<f64>, context: &str) {
+    match (a, b) {
+        (Some(a), Some(b)) => assert!((a - b).abs() < 1e-9, "{}: {} != {}", context, a, b),
+        _ => assert_eq!(a, b, "{}", context),
+    }
+}
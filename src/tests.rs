@@ -0,0 +1,881 @@
+// Behavior of the `HexGrid` API on small hand-made grids
+
+use super::*;
+
+// Grid with columns 0..width and rows 0..height, hex ids are `y * width + x`
+fn grid_with_hexes<F>(width: i32, height: i32, wrap_mode: Option<WrapMode>, hex: F) -> HexGrid
+where
+    F: Fn(i32, i32) -> HexBase,
+{
+    let hexes = (0..height).flat_map(|y| (0..width).map(move |x| (x, y))).map(|(x, y)| hex(x, y)).collect();
+    HexGrid::new(GridBorder { min_x: 0, max_x: width - 1, min_y: 0, max_y: height - 1 }, hexes, wrap_mode).unwrap()
+}
+
+fn grid_with<F>(width: i32, height: i32, wrap_mode: Option<WrapMode>, passability: F) -> HexGrid
+where
+    F: Fn(i32, i32) -> f64,
+{
+    grid_with_hexes(width, height, wrap_mode, |x, y| HexBase::new(x, y, passability(x, y), None, None))
+}
+
+fn id(grid: &HexGrid, x: i32, y: i32) -> u32 {
+    (y * grid.width + x) as u32
+}
+
+fn request(start_id: u32, target_id: u32) -> PathRequest {
+    PathRequest { start_ids: vec![start_id], target_ids: vec![target_id], target: None }
+}
+
+fn points(result: &PathResult) -> Vec<(i32, i32)> {
+    result.points.iter().map(|point| (point.x, point.y)).collect()
+}
+
+#[test]
+fn smoothing_never_crosses_impassable_hexes() {
+    // A negative passability marks the hex impassable just like 0
+    let mut grid = grid_with(8, 8, None, |x, y| if (x, y) == (3, 3) { -1.0 } else { 1.0 });
+    let (start, target) = (id(&grid, 0, 1), id(&grid, 5, 5));
+    let options = || Some(PathOptions { smooth: Some(true), ..PathOptions::default() });
+
+    let plain = grid.build_path(request(start, target), None).unwrap();
+    let smoothed = grid.build_path(request(start, target), options()).unwrap();
+    assert_eq!(smoothed.status, PathStatus::Found);
+    assert!(!points(&smoothed).contains(&(3, 3)), "{:?}", points(&smoothed));
+    assert_eq!(smoothed.total_cost, plain.total_cost);
+    assert_eq!(smoothed.points.len(), plain.points.len());
+}
+
+#[test]
+fn reachable_hexes_stay_within_a_small_budget_on_a_large_grid() {
+    let grid = grid_with(400, 400, None, |_, _| 1.0);
+    let center = Point { x: 200, y: 200 };
+    let reachable = grid.get_reachable_hexes(id(&grid, center.x, center.y), 2.0).unwrap();
+
+    assert_eq!(reachable.len(), 19);
+    for reachable_hex in &reachable {
+        let distance = grid.calculate_distance_between_hexes(center, Point { x: reachable_hex.hex.x, y: reachable_hex.hex.y });
+        assert_eq!(reachable_hex.cost, distance);
+    }
+    // Reported in row-major order
+    let ids: Vec<u32> = reachable.iter().map(|reachable_hex| reachable_hex.hex.id).collect();
+    assert!(ids.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", ids);
+}
+
+fn ids(hexes: &[Hex]) -> Vec<u32> {
+    hexes.iter().map(|hex| hex.id).collect()
+}
+
+#[test]
+fn range_is_hexagonal() {
+    let grid = grid_with(11, 11, None, |_, _| 1.0);
+    let center = Point { x: 5, y: 5 };
+
+    for range in 0..4 {
+        let area = grid.get_hexes_within_range(center, range, None);
+        assert_eq!(area.len() as i32, 1 + 3 * range * (range + 1));
+        for hex in &area {
+            assert!(grid.calculate_distance_between_hexes(center, Point { x: hex.x, y: hex.y }) <= range as f64);
+        }
+    }
+    // Corners of the old square window are farther than the range
+    let area = ids(&grid.get_hexes_within_range(center, 2, None));
+    assert!(!area.contains(&id(&grid, 3, 3)) && !area.contains(&id(&grid, 7, 7)));
+}
+
+#[test]
+fn range_is_clipped_and_wrapped() {
+    let grid = grid_with(8, 8, None, |_, _| 1.0);
+    assert_eq!(ids(&grid.get_hexes_within_range(Point { x: 0, y: 0 }, 1, None)), vec![0, 1, 8]);
+
+    let wrapped = grid_with(8, 8, Some(WrapMode::Horizontal), |_, _| 1.0);
+    let area = ids(&wrapped.get_hexes_within_range(Point { x: 0, y: 3 }, 1, None));
+    assert_eq!(area, vec![id(&wrapped, 0, 2), id(&wrapped, 1, 2), id(&wrapped, 7, 2), id(&wrapped, 0, 3), id(&wrapped, 1, 3), id(&wrapped, 7, 3), id(&wrapped, 0, 4)]);
+}
+
+// Hex ids of one of the maps returned by `get_difference_between_areas_with_neigbor_centers`
+fn difference_ids(value: &Value) -> Vec<u32> {
+    let mut ids: Vec<u32> = value.as_object().unwrap().keys().map(|key| key.parse().unwrap()).collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn area_difference_matches_the_range() {
+    let grids = [grid_with(12, 12, None, |_, _| 1.0), grid_with(12, 12, Some(WrapMode::Horizontal), |_, _| 1.0)];
+    for grid in &grids {
+        for (old_x, old_y) in [(5, 5), (6, 6), (0, 0), (11, 4), (0, 11)] {
+            let old_center = Point { x: old_x, y: old_y };
+            let old_area = ids(&grid.get_hexes_within_range(old_center, 2, None));
+            let old_cube = grid.point_to_cube(old_center);
+
+            for direction in 0..6 {
+                let new_center = grid.cube_to_point(old_cube.neighbor(direction)).unwrap();
+                if !grid.is_within_boundaries(new_center.x, new_center.y) {
+                    continue;
+                }
+                let new_area = ids(&grid.get_hexes_within_range(new_center, 2, None));
+                let difference = grid.get_difference_between_areas_with_neigbor_centers(old_center, new_center, 2);
+
+                let expected_added: Vec<u32> = new_area.iter().copied().filter(|id| !old_area.contains(id)).collect();
+                let expected_deleted: Vec<u32> = old_area.iter().copied().filter(|id| !new_area.contains(id)).collect();
+                assert_eq!(difference_ids(&difference[0]), expected_added, "{:?} -> {:?}", old_center, new_center);
+                assert_eq!(difference_ids(&difference[1]), expected_deleted, "{:?} -> {:?}", old_center, new_center);
+            }
+        }
+    }
+
+    // A step inside the grid trades one edge of the hexagon, `2 * range + 1` hexes, for the
+    // opposite one
+    let grid = &grids[0];
+    let difference = grid.get_difference_between_areas_with_neigbor_centers(Point { x: 5, y: 5 }, Point { x: 6, y: 5 }, 2);
+    assert_eq!((difference_ids(&difference[0]).len(), difference_ids(&difference[1]).len()), (5, 5));
+}
+
+// Wall along column 3 with two gaps: a cheap free PvP hex at (3, 1) and an expensive one at (3, 4)
+fn grid_with_free_pvp_corridor() -> HexGrid {
+    grid_with_hexes(7, 6, None, |x, y| match (x, y) {
+        (3, 1) => HexBase::new(x, y, 1.0, None, Some(String::from("free_pvp"))),
+        (3, 4) => HexBase::new(x, y, 0.25, None, Some(String::from("safe"))),
+        (3, _) => HexBase::new(x, y, 0.0, None, None),
+        _ => HexBase::new(x, y, 1.0, None, None),
+    })
+}
+
+#[test]
+fn banned_security_index_is_never_entered() {
+    let mut grid = grid_with_free_pvp_corridor();
+    let (start, target) = (id(&grid, 0, 1), id(&grid, 6, 1));
+    let profile = |banned| MovementProfile {
+        security_rules: Some(vec![SecurityRule { security_index: SecurityIndexType::FreePvP, multiplier: None, banned: Some(banned) }]),
+        ..MovementProfile::default()
+    };
+
+    for algorithm in [SearchAlgorithm::AStar, SearchAlgorithm::Bidirectional, SearchAlgorithm::Hierarchical] {
+        let options = |banned| Some(PathOptions { algorithm: Some(algorithm), profile: Some(profile(banned)), ..PathOptions::default() });
+
+        let allowed = grid.build_path(request(start, target), options(false)).unwrap();
+        assert!(points(&allowed).contains(&(3, 1)), "{:?}", points(&allowed));
+
+        let banned = grid.build_path(request(start, target), options(true)).unwrap();
+        assert_eq!(banned.status, PathStatus::Found);
+        assert!(points(&banned).contains(&(3, 4)), "{:?}", points(&banned));
+        assert!(!points(&banned).contains(&(3, 1)), "{:?}", points(&banned));
+    }
+}
+
+#[test]
+fn unknown_security_index_is_rejected() {
+    for security_index in ["freePvp", "FREE_PVP", ""] {
+        let hexes = vec![HexBase::new(0, 0, 1.0, None, Some(String::from(security_index)))];
+        let grid = HexGrid::new(GridBorder { min_x: 0, max_x: 0, min_y: 0, max_y: 0 }, hexes, None);
+        assert_eq!(grid.err().map(|e| e.status), Some(Status::InvalidArg.to_string()), "{:?}", security_index);
+    }
+}
+
+#[test]
+fn invalid_min_passability_is_rejected() {
+    let mut grid = grid_with(4, 4, None, |_, _| 1.0);
+    for min_passability in [-0.5, f64::NAN, f64::INFINITY] {
+        let profile = MovementProfile { min_passability: Some(min_passability), ..MovementProfile::default() };
+        let options = Some(PathOptions { profile: Some(profile), ..PathOptions::default() });
+        let result = grid.build_path(request(0, 15), options);
+        assert_eq!(result.err().map(|e| e.status), Some(Status::InvalidArg.to_string()), "{}", min_passability);
+    }
+}
+
+// Hex ids of every path the algorithm builds between far apart corners and sides of the grid,
+// one search at a time and as a batch
+fn paths_by_algorithm(grid: &mut HexGrid, algorithm: SearchAlgorithm, tie_break: TieBreak) -> Vec<Vec<u32>> {
+    let (width, height) = (grid.width, grid.height);
+    let endpoints = [
+        (id(grid, 0, 0), id(grid, width - 1, height - 1)),
+        (id(grid, width - 1, 0), id(grid, 0, height - 1)),
+        (id(grid, 0, height / 2), id(grid, width - 1, height / 2)),
+        (id(grid, width / 2, 0), id(grid, width / 2, height - 1)),
+    ];
+    let requests = || endpoints.iter().map(|&(start, target)| request(start, target)).collect::<Vec<_>>();
+    let options = || Some(PathOptions { algorithm: Some(algorithm), tie_break: Some(tie_break), ..PathOptions::default() });
+
+    let mut paths: Vec<Vec<u32>> = requests()
+        .into_iter()
+        .map(|request| grid.build_path(request, options()).unwrap().hex_ids)
+        .collect();
+    let batch = grid.build_paths_batch(requests(), options()).unwrap();
+    paths.extend(batch.into_iter().map(|result| result.hex_ids));
+    paths
+}
+
+#[test]
+fn paths_are_deterministic() {
+    // Every route between the corners of an open grid costs the same, the tie break alone picks one
+    let open = || grid_with(24, 24, None, |_, _| 1.0);
+    let rough = || grid_with(24, 24, None, |x, y| match (x * 7 + y * 13 + 5) % 11 {
+        0 => 0.0,
+        1 | 2 => 0.5,
+        _ => 1.0,
+    });
+
+    for make_grid in [&open as &dyn Fn() -> HexGrid, &rough] {
+        for algorithm in [SearchAlgorithm::AStar, SearchAlgorithm::Bidirectional, SearchAlgorithm::Hierarchical] {
+            for tie_break in [TieBreak::HigherG, TieBreak::LowerG, TieBreak::Coordinates] {
+                let mut grid = make_grid();
+                let expected = paths_by_algorithm(&mut grid, algorithm, tie_break);
+                // The batch finds the same paths as the searches run one by one
+                assert_eq!(expected[..4], expected[4..], "{:?} {:?}", algorithm, tie_break);
+
+                for _ in 0..3 {
+                    assert_eq!(paths_by_algorithm(&mut grid, algorithm, tie_break), expected, "{:?} {:?}", algorithm, tie_break);
+                    assert_eq!(paths_by_algorithm(&mut make_grid(), algorithm, tie_break), expected, "{:?} {:?}", algorithm, tie_break);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn expansion_limits_of_zero_and_one_give_partial_paths() {
+    let mut grid = grid_with(40, 40, None, |_, _| 1.0);
+    let (start, target) = (id(&grid, 2, 2), id(&grid, 35, 35));
+
+    for algorithm in [SearchAlgorithm::AStar, SearchAlgorithm::Bidirectional, SearchAlgorithm::Hierarchical] {
+        for max_expanded_nodes in [0, 1] {
+            let options = Some(PathOptions { algorithm: Some(algorithm), max_expanded_nodes: Some(max_expanded_nodes), ..PathOptions::default() });
+            let result = grid.build_path(request(start, target), options).unwrap();
+            let context = format!("{:?} limit {}", algorithm, max_expanded_nodes);
+
+            assert_eq!(result.status, PathStatus::Partial, "{}", context);
+            assert_eq!(result.expanded_nodes, max_expanded_nodes, "{}", context);
+            assert_eq!(result.hex_ids[0], start, "{}", context);
+            if max_expanded_nodes == 0 {
+                assert_eq!(result.hex_ids, vec![start], "{}", context);
+                assert_eq!(result.total_cost, 0.0, "{}", context);
+            }
+        }
+    }
+}
+
+#[test]
+fn extracted_range_crosses_the_seam_of_a_wrapped_grid() {
+    let grid = grid_with(12, 8, Some(WrapMode::Horizontal), |x, _| if x == 10 { 0.5 } else { 1.0 });
+    let center = Point { x: 0, y: 4 };
+    let extracted = grid.extract_range(center, 2).unwrap();
+    assert_eq!((extracted.min_x, extracted.max_x, extracted.min_y, extracted.max_y), (-2, 2, 2, 6));
+
+    // The area holds the same hexes as the range, the columns past the seam included
+    let mut source_ids: Vec<u32> = extracted
+        .hexes
+        .iter()
+        .filter(|hex| hex.passable)
+        .map(|hex| extracted.get_source_hex_id(hex.id).unwrap())
+        .collect();
+    source_ids.sort();
+    assert_eq!(source_ids, ids(&grid.get_hexes_within_range(center, 2, None)));
+    let seam_hex = extracted.find_hex_by_position(-2, 4).unwrap();
+    assert_eq!(extracted.get_source_hex_id(seam_hex.id), Some(id(&grid, 10, 4)));
+    assert_eq!(seam_hex.passability, 0.5);
+
+    // An area as wide as the grid keeps all of its columns and wraps
+    let extracted = grid.extract_range(center, 6).unwrap();
+    assert_eq!((extracted.min_x, extracted.max_x, extracted.wrap_x), (0, 11, true));
+    let passable = extracted.hexes.iter().filter(|hex| hex.passable).count();
+    assert_eq!(passable, grid.get_hexes_within_range(center, 6, None).len());
+}
+
+fn positions(hexes: &[Hex]) -> Vec<(i32, i32)> {
+    hexes.iter().map(|hex| (hex.x, hex.y)).collect()
+}
+
+fn distance(grid: &HexGrid, a: (i32, i32), b: (i32, i32)) -> f64 {
+    grid.calculate_distance_between_hexes(Point { x: a.0, y: a.1 }, Point { x: b.0, y: b.1 })
+}
+
+#[test]
+fn rings_are_walked_around_the_center() {
+    let grid = grid_with(15, 15, None, |_, _| 1.0);
+    let center = Point { x: 7, y: 7 };
+    assert_eq!(positions(&grid.get_ring(center, 0)), vec![(7, 7)]);
+
+    for radius in 1..5 {
+        let ring = positions(&grid.get_ring(center, radius));
+        assert_eq!(ring.len() as u32, 6 * radius);
+        for (index, &hex) in ring.iter().enumerate() {
+            assert_eq!(distance(&grid, (7, 7), hex), radius as f64);
+            // Consecutive hexes are neighbors, the walk closes on the first one
+            assert_eq!(distance(&grid, hex, ring[(index + 1) % ring.len()]), 1.0, "{:?}", ring);
+        }
+    }
+
+    // Only the hexes inside the grid are kept
+    assert_eq!(positions(&grid.get_ring(Point { x: 0, y: 0 }, 1)), vec![(0, 1), (1, 0)]);
+}
+
+#[test]
+fn spirals_cover_the_range_ring_by_ring() {
+    let grid = grid_with(15, 15, None, |_, _| 1.0);
+    for center in [Point { x: 7, y: 7 }, Point { x: 8, y: 7 }, Point { x: 1, y: 13 }] {
+        let spiral = grid.get_spiral(center, 3);
+        let distances: Vec<f64> = spiral.iter().map(|hex| distance(&grid, (center.x, center.y), (hex.x, hex.y))).collect();
+        assert!(distances.windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", distances);
+
+        let mut spiral_ids = ids(&spiral);
+        spiral_ids.sort();
+        assert_eq!(spiral_ids, ids(&grid.get_hexes_within_range(center, 3, None)));
+    }
+    assert_eq!(grid.get_spiral(Point { x: 7, y: 7 }, 3).len(), 37);
+}
+
+#[test]
+fn lines_step_through_neighbors() {
+    let grid = grid_with(16, 16, None, |_, _| 1.0);
+    for (a, b) in [((1, 1), (12, 9)), ((14, 2), (3, 13)), ((5, 5), (5, 5)), ((0, 15), (15, 0))] {
+        let line = positions(&grid.get_line(Point { x: a.0, y: a.1 }, Point { x: b.0, y: b.1 }));
+        assert_eq!((line[0], line[line.len() - 1]), (a, b));
+        assert_eq!(line.len() as f64, distance(&grid, a, b) + 1.0, "{:?}", line);
+        assert!(line.windows(2).all(|pair| distance(&grid, pair[0], pair[1]) == 1.0), "{:?}", line);
+    }
+
+    // A line along a column stays in it
+    let column = positions(&grid.get_line(Point { x: 3, y: 1 }, Point { x: 3, y: 6 }));
+    assert_eq!(column, (1..=6).map(|y| (3, y)).collect::<Vec<_>>());
+
+    // On a wrapped grid the line takes the short way across the seam
+    let wrapped = grid_with(12, 8, Some(WrapMode::Horizontal), |_, _| 1.0);
+    let line = positions(&wrapped.get_line(Point { x: 1, y: 3 }, Point { x: 10, y: 3 }));
+    assert_eq!(line.len(), 4, "{:?}", line);
+    assert!(line.iter().all(|&(x, _)| x <= 1 || x >= 10), "{:?}", line);
+}
+
+#[test]
+fn line_of_sight_is_blocked_by_the_configured_hexes() {
+    let grid = grid_with_hexes(9, 9, None, |x, y| match (x, y) {
+        (4, 4) => HexBase::new(x, y, 0.0, None, None),
+        (2, 4) => HexBase::new(x, y, 1.0, Some(String::from("battleground")), None),
+        (6, 4) => HexBase::new(x, y, 1.0, None, Some(String::from("safe"))),
+        _ => HexBase::new(x, y, 1.0, None, None),
+    });
+    let look = |from: (i32, i32), to: (i32, i32), options: Option<LineOfSightOptions>| {
+        let sight = grid.has_line_of_sight(Point { x: from.0, y: from.1 }, Point { x: to.0, y: to.1 }, options).unwrap();
+        (sight.visible, sight.blocking_hex.map(|hex| (hex.x, hex.y)))
+    };
+
+    assert_eq!(look((4, 1), (4, 7), None), (false, Some((4, 4))));
+    assert_eq!(look((3, 1), (3, 7), None), (true, None));
+    let see_through = LineOfSightOptions { block_impassable: Some(false), ..LineOfSightOptions::default() };
+    assert_eq!(look((4, 1), (4, 7), Some(see_through)), (true, None));
+
+    // The target only blocks when asked to
+    assert_eq!(look((4, 1), (4, 4), None), (true, None));
+    let include_target = LineOfSightOptions { include_target: Some(true), ..LineOfSightOptions::default() };
+    assert_eq!(look((4, 1), (4, 4), Some(include_target)), (false, Some((4, 4))));
+
+    let battleground = LineOfSightOptions { block_battleground: Some(true), ..LineOfSightOptions::default() };
+    assert_eq!(look((2, 1), (2, 7), None), (true, None));
+    assert_eq!(look((2, 1), (2, 7), Some(battleground)), (false, Some((2, 4))));
+
+    let safe = LineOfSightOptions { blocking_security_indexes: Some(vec![String::from("safe")]), ..LineOfSightOptions::default() };
+    assert_eq!(look((6, 1), (6, 7), Some(safe)), (false, Some((6, 4))));
+
+    assert!(grid.has_line_of_sight(Point { x: 0, y: 0 }, Point { x: 9, y: 0 }, None).is_err());
+}
+
+#[test]
+fn field_of_view_stops_behind_walls() {
+    let open = grid_with(15, 15, None, |_, _| 1.0);
+    let center = Point { x: 7, y: 7 };
+    let mut visible = ids(&open.compute_fov(center, 3).unwrap());
+    visible.sort();
+    assert_eq!(visible, ids(&open.get_hexes_within_range(center, 3, None)));
+
+    // A wall north of the origin is seen, the column behind it is not
+    let walled = grid_with(15, 15, None, |x, y| if (x, y) == (7, 6) { 0.0 } else { 1.0 });
+    let visible = ids(&walled.compute_fov(center, 4).unwrap());
+    assert!(visible.contains(&id(&walled, 7, 6)));
+    for y in 3..6 {
+        assert!(!visible.contains(&id(&walled, 7, y)), "(7, {}) is visible", y);
+    }
+    assert!(visible.contains(&id(&walled, 7, 8)) && visible.contains(&id(&walled, 6, 6)));
+
+    // Every hex seen across the seam of a wrapped grid is reported once
+    let wrapped = grid_with(12, 12, Some(WrapMode::Horizontal), |_, _| 1.0);
+    let mut visible = ids(&wrapped.compute_fov(Point { x: 0, y: 6 }, 3).unwrap());
+    visible.sort();
+    assert_eq!(visible, ids(&wrapped.get_hexes_within_range(Point { x: 0, y: 6 }, 3, None)));
+
+    assert!(open.compute_fov(Point { x: 15, y: 0 }, 3).is_err());
+}
+
+#[test]
+fn rotations_and_reflections_keep_distances_to_the_center() {
+    let grid = grid_with(1, 1, None, |_, _| 1.0);
+    let center = Point { x: 5, y: 4 };
+    let center_cube = grid.point_to_cube(center);
+    let points: Vec<Point> = (0..10).flat_map(|x| (0..9).map(move |y| Point { x, y })).collect();
+
+    // A step turns every direction into the next one
+    for direction in 0..6 {
+        let neighbor = grid.cube_to_point(center_cube.neighbor(direction)).unwrap();
+        let rotated = grid.rotate_point(neighbor, center, 1);
+        assert_eq!(grid.point_to_cube(rotated), center_cube.neighbor(direction + 1));
+    }
+
+    for &point in &points {
+        let to_center = grid.calculate_distance_between_hexes(point, center);
+        for steps in -6..=6 {
+            let rotated = grid.rotate_point(point, center, steps);
+            assert_eq!(grid.calculate_distance_between_hexes(rotated, center), to_center);
+            let back = grid.rotate_point(rotated, center, -steps);
+            assert_eq!((back.x, back.y), (point.x, point.y));
+        }
+
+        for axis in [HexAxis::Q, HexAxis::R, HexAxis::S] {
+            let reflected = grid.reflect_point(point, center, axis);
+            assert_eq!(grid.calculate_distance_between_hexes(reflected, center), to_center);
+            let back = grid.reflect_point(reflected, center, axis);
+            assert_eq!((back.x, back.y), (point.x, point.y));
+        }
+        // Reflecting across the Q axis keeps the column
+        assert_eq!(grid.reflect_point(point, center, HexAxis::Q).x, point.x);
+    }
+
+    let rotated = grid.rotate_points(points.clone(), center, 2);
+    for (point, rotated) in points.iter().zip(&rotated) {
+        let expected = grid.rotate_point(*point, center, 2);
+        assert_eq!((rotated.x, rotated.y), (expected.x, expected.y));
+    }
+}
+
+#[test]
+fn flow_field_leads_every_hex_to_the_closest_goal() {
+    let grid = grid_with(9, 9, None, |x, y| if x == 6 && y != 8 { 0.0 } else { 1.0 });
+    let goals = [(2, 2), (3, 7)];
+    let field = grid.compute_flow_field(goals.iter().map(|&(x, y)| id(&grid, x, y)).collect()).unwrap();
+
+    for &(x, y) in &goals {
+        let goal_id = id(&grid, x, y);
+        assert_eq!((field.get_cost(goal_id), field.get_next_hex_id(goal_id), field.get_direction(goal_id)), (Some(0.0), None, None));
+    }
+    // Left of the wall the cost is the distance to the closer goal
+    for x in 0..6 {
+        for y in 0..9 {
+            let closest = goals.iter().map(|&goal| distance(&grid, (x, y), goal)).fold(f64::INFINITY, f64::min);
+            assert_eq!(field.get_cost(id(&grid, x, y)), Some(closest), "({}, {})", x, y);
+        }
+    }
+
+    // Every step goes to a neighbor in the reported direction and lowers the cost by one
+    for hex in &grid.hexes {
+        let Some(next_id) = field.get_next_hex_id(hex.id) else {
+            continue;
+        };
+        let next = &grid.hexes[next_id as usize];
+        let step = grid.point_to_cube(Point { x: next.x, y: next.y }) - grid.point_to_cube(Point { x: hex.x, y: hex.y });
+        assert_eq!(Some(step), field.get_direction(hex.id).map(|direction| CubeCoord::direction(direction as usize)));
+        assert_eq!(field.get_cost(hex.id), field.get_cost(next_id).map(|cost| cost + 1.0));
+    }
+
+    // The wall has no cost, the hexes behind it go around through its gap
+    assert_eq!(field.get_cost(id(&grid, 6, 3)), None);
+    assert!(field.get_path(id(&grid, 6, 3)).is_empty());
+    let path = field.get_path(id(&grid, 8, 0));
+    assert!(path.contains(&id(&grid, 6, 8)), "{:?}", path);
+    assert!(goals.iter().any(|&(x, y)| id(&grid, x, y) == path[path.len() - 1]));
+
+    assert!(grid.compute_flow_field(Vec::new()).is_err());
+    assert!(grid.compute_flow_field(vec![id(&grid, 6, 0)]).is_err());
+}
+
+#[test]
+fn flow_field_crosses_the_seam_of_a_wrapped_grid() {
+    let grid = grid_with(12, 6, Some(WrapMode::Horizontal), |_, _| 1.0);
+    let field = grid.compute_flow_field(vec![id(&grid, 11, 3)]).unwrap();
+    assert_eq!(field.get_cost(id(&grid, 0, 3)), Some(1.0));
+    assert_eq!(field.get_next_hex_id(id(&grid, 0, 3)), Some(id(&grid, 11, 3)));
+    assert_eq!(field.get_cost(id(&grid, 1, 3)), Some(2.0));
+}
+
+#[test]
+fn paths_join_the_closest_start_and_target() {
+    let mut grid = grid_with(14, 14, None, |_, _| 1.0);
+    let starts = [(0, 0), (12, 12), (1, 7)];
+    let targets = [(9, 2), (4, 13)];
+    let start_ids: Vec<u32> = starts.iter().map(|&(x, y)| id(&grid, x, y)).collect();
+    let target_ids: Vec<u32> = targets.iter().map(|&(x, y)| id(&grid, x, y)).collect();
+    let closest = starts
+        .iter()
+        .flat_map(|&start| targets.iter().map(move |&target| (start, target)))
+        .map(|(start, target)| distance(&grid, start, target))
+        .fold(f64::INFINITY, f64::min);
+
+    for algorithm in [SearchAlgorithm::AStar, SearchAlgorithm::Bidirectional, SearchAlgorithm::Hierarchical] {
+        let request = PathRequest { start_ids: start_ids.clone(), target_ids: target_ids.clone(), target: None };
+        let options = Some(PathOptions { algorithm: Some(algorithm), ..PathOptions::default() });
+        let result = grid.build_path(request, options).unwrap();
+        assert_eq!(result.status, PathStatus::Found);
+        assert_eq!(result.total_cost, closest, "{:?}", algorithm);
+        assert!(start_ids.contains(&result.hex_ids[0]) && target_ids.contains(&result.hex_ids[result.hex_ids.len() - 1]));
+    }
+
+    let between = grid.build_path_between_sets(start_ids.clone(), target_ids.clone(), None).unwrap();
+    let (start, target) = (&grid.hexes[between.start_id as usize], &grid.hexes[between.target_id as usize]);
+    assert_eq!(distance(&grid, (start.x, start.y), (target.x, target.y)), closest);
+    assert_eq!((between.path[0].x, between.path[0].y), (start.x, start.y));
+    assert_eq!(between.path.len() as f64, closest + 1.0);
+
+    assert!(grid.build_path_between_sets(Vec::new(), target_ids, None).is_err());
+}
+
+#[test]
+fn paths_to_an_impassable_area_end_on_its_border() {
+    let blob = [(6, 6), (7, 6), (6, 7), (7, 7), (8, 7)];
+    let mut grid = grid_with(14, 14, None, |x, y| if blob.contains(&(x, y)) { 0.0 } else { 1.0 });
+    let request = PathRequest {
+        start_ids: vec![id(&grid, 1, 1)],
+        target_ids: vec![id(&grid, 7, 7)],
+        target: Some(PathTarget::ImpassableAreaBorder),
+    };
+    let result = grid.build_path(request, None).unwrap();
+    assert_eq!(result.status, PathStatus::Found);
+
+    let last = points(&result)[result.points.len() - 1];
+    assert!(!blob.contains(&last));
+    assert!(blob.iter().any(|&hex| distance(&grid, last, hex) == 1.0), "{:?}", last);
+    // The closest hex of the border is the one reached
+    let border = grid.get_border_passable_neighbors(Point { x: 7, y: 7 });
+    let closest = border.iter().map(|hex| distance(&grid, (1, 1), (hex.x, hex.y))).fold(f64::INFINITY, f64::min);
+    assert_eq!(result.total_cost, closest);
+}
+
+#[test]
+fn path_results_report_the_cost_of_every_step() {
+    let mut grid = grid_with(12, 12, None, |x, y| match (x, y) {
+        (_, 6) if x < 10 => 0.0,
+        (5..=7, _) => 0.5,
+        _ => 1.0,
+    });
+    let result = grid.build_path(request(id(&grid, 1, 2), id(&grid, 2, 10)), None).unwrap();
+    assert_eq!(result.status, PathStatus::Found);
+    assert_eq!((result.hex_ids.len(), result.costs.len()), (result.points.len(), result.points.len()));
+
+    assert_eq!(result.costs[0], 0.0);
+    for index in 1..result.points.len() {
+        let hex = &grid.hexes[result.hex_ids[index] as usize];
+        assert_eq!((hex.x, hex.y), (result.points[index].x, result.points[index].y));
+        assert_eq!(result.costs[index] - result.costs[index - 1], 1.0 / hex.passability);
+    }
+    assert_eq!(result.total_cost, result.costs[result.costs.len() - 1]);
+    // The detour through the gap at the end of the wall crosses the expensive columns twice
+    assert!(points(&result).contains(&(10, 6)) || points(&result).contains(&(11, 6)));
+
+    // Segments chain from the first point to the last, every step of one goes its way
+    assert_eq!(result.segments[0].start_index, 0);
+    assert_eq!(result.segments[result.segments.len() - 1].end_index as usize, result.points.len() - 1);
+    for pair in result.segments.windows(2) {
+        assert_eq!(pair[0].end_index, pair[1].start_index);
+        assert_ne!(pair[0].direction, pair[1].direction);
+    }
+    for segment in &result.segments {
+        for index in segment.start_index..segment.end_index {
+            let (from, to) = (result.points[index as usize], result.points[index as usize + 1]);
+            let step = grid.point_to_cube(to) - grid.point_to_cube(from);
+            assert_eq!(step, CubeCoord::direction(segment.direction as usize));
+        }
+    }
+
+    // An unreachable target is a status, not an error
+    let mut walled = grid_with(12, 12, None, |_, y| if y == 6 { 0.0 } else { 1.0 });
+    let result = walled.build_path(request(id(&walled, 1, 2), id(&walled, 2, 10)), None).unwrap();
+    assert_eq!((result.status, result.points.len(), result.total_cost), (PathStatus::Unreachable, 0, 0.0));
+}
+
+#[test]
+fn cost_and_length_limits_cut_the_path_short() {
+    let mut grid = grid_with(20, 20, None, |_, _| 1.0);
+    let (start, target) = (id(&grid, 2, 10), id(&grid, 17, 10));
+
+    for algorithm in [SearchAlgorithm::AStar, SearchAlgorithm::Bidirectional, SearchAlgorithm::Hierarchical] {
+        let context = format!("{:?}", algorithm);
+        let options = |max_cost, max_length| Some(PathOptions { algorithm: Some(algorithm), max_cost, max_length, ..PathOptions::default() });
+
+        let by_length = grid.build_path(request(start, target), options(None, Some(4))).unwrap();
+        assert_eq!(by_length.status, PathStatus::Partial, "{}", context);
+        assert_eq!(by_length.points.len(), 5, "{}", context);
+
+        let by_cost = grid.build_path(request(start, target), options(Some(6.5), None)).unwrap();
+        assert_eq!(by_cost.status, PathStatus::Partial, "{}", context);
+        assert!(by_cost.total_cost <= 6.5, "{}", context);
+        assert_eq!(by_cost.hex_ids[0], start, "{}", context);
+
+        // Limits the path fits in change nothing
+        let unlimited = grid.build_path(request(start, target), options(None, None)).unwrap();
+        let fitting = options(Some(unlimited.total_cost), Some(unlimited.points.len() as u32 - 1));
+        let roomy = grid.build_path(request(start, target), fitting).unwrap();
+        assert_eq!((roomy.status, roomy.total_cost), (PathStatus::Found, unlimited.total_cost), "{}", context);
+    }
+
+    // The partial path of A* ends on the explored hex closest to the target
+    let options = Some(PathOptions { max_cost: Some(6.5), ..PathOptions::default() });
+    let partial = grid.build_path(request(start, target), options).unwrap();
+    let last = points(&partial)[partial.points.len() - 1];
+    assert_eq!(distance(&grid, last, (17, 10)), 9.0, "{:?}", last);
+}
+
+#[test]
+fn bidirectional_search_matches_astar_and_stops_on_enclosed_targets() {
+    // Walls with alternating gaps make the route zig-zag down the grid
+    let mut grid = grid_with(30, 30, None, |x, y| match (y % 6, (y / 6) % 2) {
+        (3, 0) if x < 26 => 0.0,
+        (3, 1) if x > 3 => 0.0,
+        _ => if (x + y) % 7 == 0 { 0.5 } else { 1.0 },
+    });
+    let bidirectional = || Some(PathOptions { algorithm: Some(SearchAlgorithm::Bidirectional), ..PathOptions::default() });
+    for (start, target) in [((1, 0), (28, 29)), ((15, 1), (2, 28)), ((5, 5), (5, 5))] {
+        let (start, target) = (id(&grid, start.0, start.1), id(&grid, target.0, target.1));
+        let expected = grid.build_path(request(start, target), None).unwrap();
+        let result = grid.build_path(request(start, target), bidirectional()).unwrap();
+        assert_eq!((result.status, result.total_cost), (PathStatus::Found, expected.total_cost));
+        assert_eq!((result.hex_ids[0], result.hex_ids[result.hex_ids.len() - 1]), (start, target));
+    }
+
+    // The backward search runs out of hexes right away around an enclosed target
+    let ring = grid.get_ring(Point { x: 20, y: 20 }, 1);
+    let mut enclosed = grid_with(40, 40, None, |x, y| if ring.iter().any(|hex| (hex.x, hex.y) == (x, y)) { 0.0 } else { 1.0 });
+    let result = enclosed.build_path(request(id(&enclosed, 1, 1), id(&enclosed, 20, 20)), bidirectional()).unwrap();
+    assert_eq!(result.status, PathStatus::Unreachable);
+    assert!(result.expanded_nodes <= 2, "{}", result.expanded_nodes);
+}
+
+// Passable walk from the first hex of the path to the last one
+fn assert_walk(grid: &HexGrid, result: &PathResult) {
+    let path = points(result);
+    for pair in path.windows(2) {
+        assert_eq!(distance(grid, pair[0], pair[1]), 1.0, "{:?}", path);
+    }
+    for &(x, y) in &path {
+        assert!(grid.hexes[id(grid, x, y) as usize].passable, "{:?}", path);
+    }
+}
+
+#[test]
+fn hierarchical_paths_follow_passability_changes() {
+    // Two rooms joined by a single door at (12, 10)
+    let mut grid = grid_with(24, 20, None, |x, y| if x == 12 && y != 10 { 0.0 } else { 1.0 });
+    grid.build_hierarchy(Some(6)).unwrap();
+    let hierarchical = || Some(PathOptions { algorithm: Some(SearchAlgorithm::Hierarchical), ..PathOptions::default() });
+    let (start, target) = (id(&grid, 2, 3), id(&grid, 21, 17));
+
+    let result = grid.build_path(request(start, target), hierarchical()).unwrap();
+    assert_eq!(result.status, PathStatus::Found);
+    assert_walk(&grid, &result);
+    assert!(points(&result).contains(&(12, 10)));
+    let optimal = grid.build_path(request(start, target), None).unwrap().total_cost;
+    assert!(result.total_cost >= optimal && result.total_cost <= optimal * 1.5, "{} against {}", result.total_cost, optimal);
+
+    // Closing the door cuts the rooms apart, opening another one joins them again
+    grid.set_hex_passability(id(&grid, 12, 10), 0.0).unwrap();
+    let result = grid.build_path(request(start, target), hierarchical()).unwrap();
+    assert_eq!(result.status, PathStatus::Unreachable);
+
+    grid.set_hex_passability(id(&grid, 12, 2), 1.0).unwrap();
+    let result = grid.build_path(request(start, target), hierarchical()).unwrap();
+    assert_eq!(result.status, PathStatus::Found);
+    assert_walk(&grid, &result);
+    assert!(points(&result).contains(&(12, 2)));
+
+    assert!(grid.build_hierarchy(Some(1)).is_err());
+}
+
+#[test]
+fn planners_repair_their_paths_after_changes() {
+    let mut grid = grid_with(20, 20, None, |x, y| if (x + 2 * y) % 9 == 0 { 0.5 } else { 1.0 });
+    let (start, target) = (id(&grid, 1, 1), id(&grid, 18, 17));
+    let planner = grid.create_planner(request(start, target)).unwrap();
+
+    let first = grid.get_planner_path(planner).unwrap();
+    assert_eq!(first.status, PathStatus::Found);
+    assert_eq!(first.total_cost, grid.build_path(request(start, target), None).unwrap().total_cost);
+    // Nothing changed, nothing is expanded again
+    assert_eq!(grid.get_planner_path(planner).unwrap().expanded_nodes, 0);
+
+    // Blocking a hex of the path reroutes around it for the cost of a fresh search
+    let blocked = first.hex_ids[first.hex_ids.len() / 2];
+    grid.set_hex_passability(blocked, 0.0).unwrap();
+    let repaired = grid.get_planner_path(planner).unwrap();
+    assert!(!repaired.hex_ids.contains(&blocked));
+    assert_walk(&grid, &repaired);
+    assert_eq!(repaired.total_cost, grid.build_path(request(start, target), None).unwrap().total_cost);
+    assert!(repaired.expanded_nodes > 0 && repaired.expanded_nodes < first.expanded_nodes, "{} {}", repaired.expanded_nodes, first.expanded_nodes);
+
+    // After a step along the path the rest of it stays the same
+    grid.move_planner_start(planner, repaired.hex_ids[1]).unwrap();
+    let moved = grid.get_planner_path(planner).unwrap();
+    assert_eq!(moved.total_cost, grid.build_path(request(repaired.hex_ids[1], target), None).unwrap().total_cost);
+    assert_eq!(moved.hex_ids[0], repaired.hex_ids[1]);
+
+    assert!(grid.remove_planner(planner));
+    assert!(!grid.remove_planner(planner));
+    assert!(grid.get_planner_path(planner).is_err());
+    let two_starts = PathRequest { start_ids: vec![start, id(&grid, 2, 1)], target_ids: vec![target], target: None };
+    assert!(grid.create_planner(two_starts).is_err());
+}
+
+// Task `build_path_async` would queue for the request
+fn path_task(grid: &mut HexGrid, request: &PathRequest, options: PathOptions) -> PathTask {
+    let endpoints = grid.resolve_path_request(request).unwrap();
+    PathTask::new(grid.search_view(&options).unwrap(), endpoints, options)
+}
+
+fn task_hex_ids(grid: &HexGrid, task: &mut PathTask) -> (PathStatus, Vec<u32>) {
+    let outcome = task.compute().unwrap();
+    (outcome.status, outcome.path.iter().map(|&(x, y)| (y as i32 * grid.width + x as i32) as u32).collect())
+}
+
+#[test]
+fn async_searches_run_on_the_grid_they_were_started_on() {
+    let mut grid = grid_with(16, 16, None, |_, _| 1.0);
+    let (start, target) = (id(&grid, 1, 8), id(&grid, 14, 8));
+
+    for algorithm in [SearchAlgorithm::AStar, SearchAlgorithm::Bidirectional, SearchAlgorithm::Hierarchical] {
+        let options = || PathOptions { algorithm: Some(algorithm), ..PathOptions::default() };
+        let before = grid.build_path(request(start, target), Some(options())).unwrap();
+        let mut task = path_task(&mut grid, &request(start, target), options());
+
+        // A wall raised after the task was queued does not reach it
+        for y in 0..15 {
+            grid.set_hex_passability(id(&grid, 8, y), 0.0).unwrap();
+        }
+        assert_eq!(task_hex_ids(&grid, &mut task), (PathStatus::Found, before.hex_ids.clone()), "{:?}", algorithm);
+
+        // Tasks queued afterwards see it, like the synchronous search
+        let after = grid.build_path(request(start, target), Some(options())).unwrap();
+        assert!(after.hex_ids.contains(&id(&grid, 8, 15)), "{:?}", algorithm);
+        let mut task = path_task(&mut grid, &request(start, target), options());
+        assert_eq!(task_hex_ids(&grid, &mut task), (PathStatus::Found, after.hex_ids), "{:?}", algorithm);
+
+        for y in 0..15 {
+            grid.set_hex_passability(id(&grid, 8, y), 1.0).unwrap();
+        }
+    }
+}
+
+#[test]
+fn batches_return_results_in_request_order() {
+    let mut grid = grid_with(30, 30, None, |x, y| if x == 20 && y > 0 { 0.0 } else if (x * y) % 5 == 1 { 0.5 } else { 1.0 });
+    let passable: Vec<u32> = grid.hexes.iter().filter(|hex| hex.passable).map(|hex| hex.id).collect();
+    // Long and short requests mixed, so that threads finish them out of order
+    let pairs: Vec<(u32, u32)> = (0..120)
+        .map(|index| (passable[(index * 37) % passable.len()], passable[(index * 101 + 7) % passable.len()]))
+        .collect();
+    let requests = || pairs.iter().map(|&(start, target)| request(start, target)).collect::<Vec<_>>();
+    let options = || Some(PathOptions { max_expanded_nodes: Some(300), ..PathOptions::default() });
+
+    let batch = grid.build_paths_batch(requests(), options()).unwrap();
+    assert_eq!(batch.len(), pairs.len());
+    for (index, (result, &(start, target))) in batch.iter().zip(&pairs).enumerate() {
+        let single = grid.build_path(request(start, target), options()).unwrap();
+        assert_eq!((result.status, &result.hex_ids, result.total_cost), (single.status, &single.hex_ids, single.total_cost), "request {}", index);
+    }
+    assert!(batch.iter().any(|result| result.status == PathStatus::Partial));
+
+    // One invalid request fails the whole batch and is named in the error
+    let mut invalid = requests();
+    invalid[37].start_ids = vec![id(&grid, 20, 5)];
+    let error = grid.build_paths_batch(invalid, options()).err().unwrap();
+    assert_eq!(error.status, Status::InvalidArg.to_string());
+    assert!(error.reason.starts_with("Request 37:"), "{}", error.reason);
+
+    assert!(grid.build_paths_batch(Vec::new(), None).unwrap().is_empty());
+}
+
+#[test]
+fn waypoint_paths_visit_every_stop_in_order() {
+    let mut grid = grid_with(18, 18, None, |x, y| if y == 8 && x > 2 { 0.0 } else if x == 9 { 0.5 } else { 1.0 });
+    let stops = [(1, 1), (15, 2), (3, 12), (14, 15)];
+    let stop_ids: Vec<u32> = stops.iter().map(|&(x, y)| id(&grid, x, y)).collect();
+
+    let via = grid.build_path_via(stop_ids[0], stop_ids[1..3].to_vec(), stop_ids[3], None).unwrap();
+    assert_eq!(via.path.status, PathStatus::Found);
+    assert_walk(&grid, &via.path);
+    assert_eq!(via.legs.len(), 3);
+    assert_eq!((via.legs[0].start_index, via.legs[2].end_index as usize), (0, via.path.points.len() - 1));
+
+    let mut total = 0.0;
+    for (index, leg) in via.legs.iter().enumerate() {
+        assert_eq!(via.path.hex_ids[leg.start_index as usize], stop_ids[index]);
+        assert_eq!(via.path.hex_ids[leg.end_index as usize], stop_ids[index + 1]);
+        // Every leg is the cheapest path between its stops
+        let alone = grid.build_path(request(stop_ids[index], stop_ids[index + 1]), None).unwrap();
+        assert_eq!(leg.cost, alone.total_cost, "leg {}", index);
+        total += leg.cost;
+    }
+    assert_eq!(via.path.total_cost, total);
+
+    // The limits apply to every leg, the detour around the wall is too long. A path cut short
+    // keeps the legs it completed
+    let options = Some(PathOptions { max_length: Some(16), ..PathOptions::default() });
+    let cut = grid.build_path_via(stop_ids[0], stop_ids[1..3].to_vec(), stop_ids[3], options).unwrap();
+    assert_eq!(cut.path.status, PathStatus::Partial);
+    assert!(cut.legs.len() < 3 && !cut.legs.is_empty());
+
+    assert!(grid.build_path_via(stop_ids[0], vec![id(&grid, 5, 8)], stop_ids[3], None).is_err());
+}
+
+#[test]
+fn alternative_paths_take_distinct_routes_cheapest_first() {
+    // An island leaves a corridor above it and a slightly longer one below
+    let mut grid = grid_with(21, 11, None, |x, y| if x <= 2 || x >= 18 || y == 1 || y == 9 { 1.0 } else { 0.0 });
+    let (start, target) = (id(&grid, 1, 4), id(&grid, 19, 4));
+
+    let paths = grid.build_k_paths(start, target, 3, 1.0, None).unwrap();
+    assert_eq!(paths.len(), 3);
+    assert_eq!(paths[0].total_cost, grid.build_path(request(start, target), None).unwrap().total_cost);
+    for (index, path) in paths.iter().enumerate() {
+        assert_eq!(path.status, PathStatus::Found);
+        assert_walk(&grid, path);
+        assert_eq!((path.hex_ids[0], path.hex_ids[path.hex_ids.len() - 1]), (start, target));
+        // Penalties only steer the searches, every hex still costs its own 1.0
+        assert_eq!(path.total_cost, (path.hex_ids.len() - 1) as f64);
+        assert!(paths[..index].iter().all(|other| other.hex_ids != path.hex_ids), "path {} repeats", index);
+    }
+    assert!(paths.windows(2).all(|pair| pair[0].total_cost <= pair[1].total_cost));
+
+    // The island is passed on both sides
+    let above = |path: &PathResult| points(path).contains(&(10, 1));
+    let below = |path: &PathResult| points(path).contains(&(10, 9));
+    assert!(paths.iter().any(above) && paths.iter().any(below));
+
+    for (k, diversity) in [(0, 1.0), (17, 1.0), (2, 0.0), (2, f64::NAN)] {
+        assert!(grid.build_k_paths(start, target, k, diversity, None).is_err(), "{} {}", k, diversity);
+    }
+    let mut walled = grid_with(10, 10, None, |x, _| if x == 5 { 0.0 } else { 1.0 });
+    assert!(walled.build_k_paths(id(&walled, 1, 1), id(&walled, 8, 8), 3, 1.0, None).unwrap().is_empty());
+}
+
+#[test]
+fn smoothed_paths_follow_hex_lines_at_no_extra_cost() {
+    let grids = [grid_with(16, 16, None, |_, _| 1.0), grid_with(16, 16, None, |x, y| if (x * 3 + y * 5) % 7 == 0 { 0.5 } else { 1.0 })];
+    let smooth = || Some(PathOptions { smooth: Some(true), ..PathOptions::default() });
+
+    for (index, mut grid) in grids.into_iter().enumerate() {
+        for (from, to) in [((0, 0), (15, 15)), ((1, 14), (14, 2)), ((0, 7), (15, 8)), ((3, 0), (9, 15))] {
+            let (start, target) = (id(&grid, from.0, from.1), id(&grid, to.0, to.1));
+            let plain = grid.build_path(request(start, target), None).unwrap();
+            let smoothed = grid.build_path(request(start, target), smooth()).unwrap();
+            let context = format!("grid {} {:?} -> {:?}", index, from, to);
+
+            assert_eq!(smoothed.status, PathStatus::Found, "{}", context);
+            assert_walk(&grid, &smoothed);
+            assert_eq!(smoothed.points.len(), plain.points.len(), "{}", context);
+            assert!(smoothed.total_cost <= plain.total_cost + 1e-9, "{}", context);
+            // Nothing stands in the way on the open grid, so the whole path becomes one line
+            if index == 0 {
+                let line: Vec<(i32, i32)> = grid.get_line(Point { x: from.0, y: from.1 }, Point { x: to.0, y: to.1 }).iter().map(|hex| (hex.x, hex.y)).collect();
+                assert_eq!(points(&smoothed), line, "{}", context);
+            }
+
+            // Segments run back to back over the whole path and change direction between them
+            assert_eq!(smoothed.segments[0].start_index, 0, "{}", context);
+            assert_eq!(smoothed.segments[smoothed.segments.len() - 1].end_index as usize, smoothed.points.len() - 1, "{}", context);
+            assert!(smoothed.segments.windows(2).all(|pair| pair[0].end_index == pair[1].start_index && pair[0].direction != pair[1].direction), "{}", context);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use napi_derive::napi;
+
+use crate::coords::CubeCoord;
+use crate::Hex;
+
+#[napi(object)]
+#[derive(Default)]
+pub struct LineOfSightOptions {
+    /// Impassable hexes block the view (default: true)
+    pub block_impassable: Option<bool>,
+    /// Battleground hexes block the view (default: false)
+    pub block_battleground: Option<bool>,
+    /// Hexes with one of these security indexes block the view
+    pub blocking_security_indexes: Option<Vec<String>>,
+    /// Whether the target hex itself can block the view (default: false)
+    pub include_target: Option<bool>,
+}
+
+#[napi(object)]
+pub struct LineOfSight {
+    pub visible: bool,
+    pub blocking_hex: Option<Hex>,
+}
+
+impl LineOfSightOptions {
+    pub fn is_blocking(&self, hex: &Hex, passable: bool) -> bool {
+        if self.block_impassable.unwrap_or(true) && !passable {
+            return true;
+        }
+        if self.block_battleground.unwrap_or(false) && hex.battleground {
+            return true;
+        }
+        match &self.blocking_security_indexes {
+            Some(indexes) => indexes.contains(&hex.security_index),
+            None => false,
+        }
+    }
+}
+
+/// Hex shadowcasting: walks the rings around `origin` and keeps the angular intervals hidden
+/// behind opaque hexes, measured in turns from the first hex of every ring. A hex is visible
+/// unless its whole interval is already in shadow, so walls at the edge of the view are seen.
+///
+/// `is_opaque` returns `None` for positions outside the map, those are neither seen nor block.
+pub fn shadowcast<F>(origin: CubeCoord, radius: u32, mut is_opaque: F) -> Vec<CubeCoord>
+where
+    F: FnMut(CubeCoord) -> Option<bool>,
+{
+    let mut visible = vec![origin];
+    let mut shadows: Vec<(f64, f64)> = Vec::new();
+
+    for ring_radius in 1..=radius {
+        let ring = origin.ring(ring_radius);
+        let step = 1.0 / ring.len() as f64;
+        let mut new_shadows = Vec::new();
+
+        for (index, cube) in ring.into_iter().enumerate() {
+            let opaque = match is_opaque(cube) {
+                Some(opaque) => opaque,
+                None => continue,
+            };
+
+            let start = (index as f64 - 0.5) * step;
+            let end = (index as f64 + 0.5) * step;
+            // The first hex of a ring straddles the zero angle
+            let parts = if start < 0.0 {
+                vec![(0.0, end), (1.0 + start, 1.0)]
+            } else {
+                vec![(start, end)]
+            };
+
+            if !parts.iter().all(|&part| is_in_shadow(&shadows, part)) {
+                visible.push(cube);
+            }
+            if opaque {
+                new_shadows.extend(parts);
+            }
+        }
+
+        if !new_shadows.is_empty() {
+            shadows.extend(new_shadows);
+            shadows = merge_shadows(shadows);
+        }
+        if shadows.len() == 1 && shadows[0].0 <= 0.0 && shadows[0].1 >= 1.0 {
+            break;
+        }
+    }
+
+    visible
+}
+
+fn is_in_shadow(shadows: &[(f64, f64)], (start, end): (f64, f64)) -> bool {
+    const EPSILON: f64 = 1e-9;
+    shadows.iter().any(|&(s, e)| s <= start + EPSILON && end <= e + EPSILON)
+}
+
+fn merge_shadows(mut shadows: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    const EPSILON: f64 = 1e-9;
+    shadows.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut merged: Vec<(f64, f64)> = Vec::with_capacity(shadows.len());
+    for (start, end) in shadows {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 + EPSILON => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
@@ -0,0 +1,34 @@
+use crate::path::{PathOptions, PathStatus, SearchEndpoints, SearchOutcome};
+use crate::search_view::SearchView;
+
+/// Searches the legs one after another on the same view and joins their paths, every leg starts
+/// on the node the previous one ended on. Returns the joined path together with the index of the
+/// last node of every leg searched.
+///
+/// Limits of `options` apply to every leg separately. A partial leg ends the path, an
+/// unreachable one makes the whole path unreachable.
+pub fn search_legs(view: &SearchView, legs: &[SearchEndpoints], options: &PathOptions) -> (SearchOutcome, Vec<usize>) {
+    let mut path: Vec<(usize, usize)> = Vec::new();
+    let mut leg_ends = Vec::with_capacity(legs.len());
+    let mut expanded_nodes = 0;
+
+    for endpoints in legs {
+        let outcome = view.search(endpoints, options);
+        expanded_nodes += outcome.expanded_nodes;
+
+        if outcome.status == PathStatus::Unreachable {
+            return (SearchOutcome { status: PathStatus::Unreachable, path: Vec::new(), expanded_nodes }, Vec::new());
+        }
+
+        // The first node of a leg is the last one of the previous leg
+        let skip = if path.is_empty() { 0 } else { 1 };
+        path.extend(outcome.path.into_iter().skip(skip));
+        leg_ends.push(path.len() - 1);
+
+        if outcome.status == PathStatus::Partial {
+            return (SearchOutcome { status: PathStatus::Partial, path, expanded_nodes }, leg_ends);
+        }
+    }
+
+    (SearchOutcome { status: PathStatus::Found, path, expanded_nodes }, leg_ends)
+}